@@ -0,0 +1,92 @@
+// Compares the day3 `Matrix` and `BitTerrain` layouts on a generated map.
+//
+//     cargo run --release --example day3_terrain -- [WIDTH] [HEIGHT] [ITERATIONS]
+
+use std::env;
+use std::time::Instant;
+
+use aoc_2020::bench::Stats;
+use aoc_2020::day3::{self, BitTerrain, Day3, Edges, Place, Terrain, SLOPES};
+use aoc_2020::Solution;
+
+// Roughly one tree in four, the same map every run.
+fn generate(width: usize, height: usize) -> String {
+    let mut state = 0x2020_u64;
+    let mut raw = String::with_capacity((width + 1) * height);
+
+    for _ in 0..height {
+        for _ in 0..width {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            raw.push(if state >> 62 == 0 { '#' } else { '.' });
+        }
+        raw.push('\n');
+    }
+
+    raw
+}
+
+fn time<T>(iterations: usize, f: impl Fn() -> T) -> (T, Stats) {
+    let mut samples = Vec::with_capacity(iterations);
+    let mut result = None;
+
+    for _ in 0..iterations {
+        let timer = Instant::now();
+        result = Some(f());
+        samples.push(timer.elapsed());
+    }
+
+    (result.unwrap(), Stats::from_samples(&samples))
+}
+
+fn trees<M: Terrain>(m: &M) -> usize {
+    SLOPES
+        .iter()
+        .map(|&slope| {
+            day3::walk(m, slope, Edges::WrapHorizontal)
+                .filter(|step| step.place == Place::Tree)
+                .count()
+        })
+        .product()
+}
+
+fn report(label: &str, stats: Stats) {
+    println!(
+        "  {}: min {:?}, median {:?}, mean {:?}, stddev {:?}",
+        label, stats.min, stats.median, stats.mean, stats.stddev
+    );
+}
+
+fn main() {
+    let arg = |i: usize, default: usize| {
+        env::args()
+            .nth(i)
+            .map_or(default, |a| a.parse().expect("expected a number"))
+    };
+    let (width, height, iterations) = (arg(1, 1_000_000), arg(2, 323), arg(3, 5));
+    let raw = generate(width, height);
+
+    println!("{} x {} map, {} iterations", width, height, iterations);
+
+    let (matrix, parse) = time(iterations, || Day3::parse(&raw).unwrap());
+    let (matrix_trees, walk) = time(iterations, || trees(&matrix));
+    println!(
+        "Matrix ({} MiB)",
+        (width * height * std::mem::size_of::<Place>()) >> 20
+    );
+    report("parse", parse);
+    report("walk", walk);
+    drop(matrix);
+
+    let (bits, parse) = time(iterations, || BitTerrain::parse(&raw).unwrap());
+    let (bit_trees, walk) = time(iterations, || trees(&bits));
+    println!(
+        "BitTerrain ({} MiB)",
+        (width.div_ceil(64) * height * 8) >> 20
+    );
+    report("parse", parse);
+    report("walk", walk);
+
+    assert_eq!(matrix_trees, bit_trees, "the layouts disagree");
+}
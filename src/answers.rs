@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use toml::Value;
+
+pub const DEFAULT_ANSWERS_FILE: &str = "answers.toml";
+
+// Known answers, keyed by day then part:
+//
+//     [day1]
+//     part_1 = 514579
+//     part_2 = "241861950"
+//
+// Strings and integers are both accepted, since some answers don't fit in an i64.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Answers(BTreeMap<(u16, u8), String>);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Verdict {
+    Pass,
+    Fail { expected: String },
+    Unknown,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Verdict::Pass => write!(f, "PASS"),
+            Verdict::Fail { expected } => write!(f, "FAIL (expected {})", expected),
+            Verdict::Unknown => write!(f, "UNKNOWN"),
+        }
+    }
+}
+
+fn parse_key(key: &str, prefix: &str) -> Option<u16> {
+    key.strip_prefix(prefix)?.parse::<u16>().ok()
+}
+
+impl FromStr for Answers {
+    type Err = String;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let table = raw.parse::<Value>().map_err(|e| e.to_string())?;
+        let mut answers = BTreeMap::new();
+
+        for (day_key, parts) in table.as_table().into_iter().flatten() {
+            let day = parse_key(day_key, "day")
+                .ok_or_else(|| format!("[{}] should look like `day<N>`", day_key))?;
+            let parts = parts
+                .as_table()
+                .ok_or_else(|| format!("[{}] should be a table of parts", day_key))?;
+
+            for (part_key, answer) in parts {
+                let part = match parse_key(part_key, "part_") {
+                    Some(p @ 1..=2) => p as u8,
+                    _ => {
+                        return Err(format!(
+                            "[{}.{}] should be part_1 or part_2",
+                            day_key, part_key
+                        ))
+                    }
+                };
+                let answer = match answer {
+                    Value::String(s) => s.clone(),
+                    Value::Integer(i) => i.to_string(),
+                    x => {
+                        return Err(format!(
+                            "[{}.{}] has unsupported value {}",
+                            day_key, part_key, x
+                        ))
+                    }
+                };
+
+                answers.insert((day, part), answer);
+            }
+        }
+
+        Ok(Answers(answers))
+    }
+}
+
+impl Answers {
+    pub fn check(&self, day: u16, part: u8, answer: &str) -> Verdict {
+        match self.0.get(&(day, part)) {
+            Some(expected) if expected == answer => Verdict::Pass,
+            Some(expected) => Verdict::Fail {
+                expected: expected.clone(),
+            },
+            None => Verdict::Unknown,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use indoc::indoc;
+
+    const RAW_ANSWERS: &str = indoc!(
+        r#"
+        # Comments are fine
+        [day1]
+        part_1 = 514579
+        part_2 = "241861950"
+
+        [day9]
+        part_1 = 127
+        "#
+    );
+
+    #[test]
+    fn check() {
+        let answers = RAW_ANSWERS.parse::<Answers>().unwrap();
+
+        assert_eq!(answers.check(1, 1, "514579"), Verdict::Pass);
+        assert_eq!(answers.check(1, 2, "241861950"), Verdict::Pass);
+        assert_eq!(
+            answers.check(9, 1, "128"),
+            Verdict::Fail {
+                expected: "127".to_string()
+            }
+        );
+        assert_eq!(answers.check(9, 2, "62"), Verdict::Unknown);
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert!("[one]\npart_1 = 1".parse::<Answers>().is_err());
+        assert!("[day1]\npart_3 = 1".parse::<Answers>().is_err());
+        assert!("[day1]\npart_1 = 1.5".parse::<Answers>().is_err());
+        assert!("[day1".parse::<Answers>().is_err());
+    }
+}
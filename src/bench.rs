@@ -0,0 +1,268 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::str::FromStr;
+use std::time::Duration;
+
+use toml::Value;
+
+use crate::error::Result;
+use crate::solution::Runnable;
+
+// Medians more than this much slower than the baseline get flagged.
+const REGRESSION_THRESHOLD: f64 = 1.1;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stats {
+    pub min: Duration,
+    pub median: Duration,
+    pub mean: Duration,
+    pub stddev: Duration,
+}
+
+impl Stats {
+    pub fn from_samples(samples: &[Duration]) -> Stats {
+        assert!(!samples.is_empty(), "can't summarize zero samples");
+
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let n = sorted.len();
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        } else {
+            sorted[n / 2]
+        };
+
+        let nanos = sorted
+            .iter()
+            .map(|d| d.as_nanos() as f64)
+            .collect::<Vec<_>>();
+        let mean = nanos.iter().sum::<f64>() / n as f64;
+        let variance = nanos.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / n as f64;
+
+        Stats {
+            min: sorted[0],
+            median,
+            mean: Duration::from_nanos(mean.round() as u64),
+            stddev: Duration::from_nanos(variance.sqrt().round() as u64),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bench {
+    pub day: u16,
+    pub part: u8,
+    pub iterations: usize,
+    pub parse: Stats,
+    pub solve: Stats,
+}
+
+pub fn bench(solution: &dyn Runnable, part: u8, raw: &str, iterations: usize) -> Result<Bench> {
+    let mut parse_times = Vec::with_capacity(iterations);
+    let mut solve_times = Vec::with_capacity(iterations);
+
+    for _ in 0..iterations.max(1) {
+        let run = solution.run(part, raw)?;
+        parse_times.push(run.parse_time);
+        solve_times.push(run.solve_time);
+    }
+
+    Ok(Bench {
+        day: solution.day(),
+        part,
+        iterations: parse_times.len(),
+        parse: Stats::from_samples(&parse_times),
+        solve: Stats::from_samples(&solve_times),
+    })
+}
+
+// Median parse and solve times from an earlier run, stored as TOML:
+//
+//     [day1.part_1]
+//     parse_ns = 24810
+//     solve_ns = 2013
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Baseline(BTreeMap<(u16, u8), (Duration, Duration)>);
+
+impl Baseline {
+    pub fn from_benches(benches: &[Bench]) -> Baseline {
+        Baseline(
+            benches
+                .iter()
+                .map(|b| ((b.day, b.part), (b.parse.median, b.solve.median)))
+                .collect(),
+        )
+    }
+
+    pub fn to_toml(&self) -> String {
+        let mut out = String::new();
+        for ((day, part), (parse, solve)) in &self.0 {
+            writeln!(
+                out,
+                "[day{}.part_{}]\nparse_ns = {}\nsolve_ns = {}\n",
+                day,
+                part,
+                parse.as_nanos(),
+                solve.as_nanos()
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+impl FromStr for Baseline {
+    type Err = String;
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        let table = raw.parse::<Value>().map_err(|e| e.to_string())?;
+        let mut baseline = BTreeMap::new();
+
+        for (day_key, parts) in table.as_table().into_iter().flatten() {
+            let day = day_key
+                .strip_prefix("day")
+                .and_then(|d| d.parse::<u16>().ok())
+                .ok_or_else(|| format!("[{}] should look like `day<N>`", day_key))?;
+
+            for (part_key, timings) in parts.as_table().into_iter().flatten() {
+                let part = match part_key.as_str() {
+                    "part_1" => 1,
+                    "part_2" => 2,
+                    _ => {
+                        return Err(format!(
+                            "[{}.{}] should be part_1 or part_2",
+                            day_key, part_key
+                        ))
+                    }
+                };
+                let nanos = |key: &str| {
+                    timings
+                        .get(key)
+                        .and_then(|v| v.as_integer())
+                        .filter(|&n| n >= 0)
+                        .map(|n| Duration::from_nanos(n as u64))
+                        .ok_or_else(|| format!("[{}.{}] is missing {}", day_key, part_key, key))
+                };
+
+                baseline.insert((day, part), (nanos("parse_ns")?, nanos("solve_ns")?));
+            }
+        }
+
+        Ok(Baseline(baseline))
+    }
+}
+
+fn change(now: Duration, before: Duration) -> String {
+    if before.as_nanos() == 0 {
+        return "n/a".to_string();
+    }
+
+    let ratio = now.as_nanos() as f64 / before.as_nanos() as f64;
+    format!(
+        "{:+.1}%{}",
+        (ratio - 1.0) * 100.0,
+        if ratio > REGRESSION_THRESHOLD {
+            " SLOWER"
+        } else {
+            ""
+        }
+    )
+}
+
+pub fn render(benches: &[Bench], baseline: Option<&Baseline>) -> String {
+    let mut out = String::new();
+
+    for b in benches {
+        writeln!(
+            out,
+            "Day {} part {} ({} iterations)",
+            b.day, b.part, b.iterations
+        )
+        .unwrap();
+        for (label, stats, index) in &[("parse", b.parse, 0), ("solve", b.solve, 1)] {
+            write!(
+                out,
+                "  {}: min {:?}, median {:?}, mean {:?}, stddev {:?}",
+                label, stats.min, stats.median, stats.mean, stats.stddev
+            )
+            .unwrap();
+
+            if let Some(&before) = baseline.and_then(|base| base.0.get(&(b.day, b.part))) {
+                let before = if *index == 0 { before.0 } else { before.1 };
+                write!(
+                    out,
+                    " | baseline median {:?} ({})",
+                    before,
+                    change(stats.median, before)
+                )
+                .unwrap();
+            }
+            writeln!(out).unwrap();
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solution;
+
+    fn ns(n: u64) -> Duration {
+        Duration::from_nanos(n)
+    }
+
+    #[test]
+    fn stats() {
+        let stats = Stats::from_samples(&[ns(4), ns(2), ns(8), ns(6)]);
+
+        assert_eq!(
+            stats,
+            Stats {
+                min: ns(2),
+                median: ns(5),
+                mean: ns(5),
+                stddev: ns(2),
+            }
+        );
+        assert_eq!(Stats::from_samples(&[ns(3), ns(1), ns(2)]).median, ns(2));
+    }
+
+    #[test]
+    fn bench_runs_every_iteration() {
+        let day1 = solution::find(1).unwrap();
+        let result = bench(day1, 2, "1721\n979\n366\n299\n675\n1456", 5).unwrap();
+
+        assert_eq!((result.day, result.part, result.iterations), (1, 2, 5));
+        assert!(bench(day1, 1, "nope", 5).is_err());
+    }
+
+    #[test]
+    fn baseline_round_trip() {
+        let stats = Stats::from_samples(&[ns(100)]);
+        let benches = vec![Bench {
+            day: 9,
+            part: 2,
+            iterations: 1,
+            parse: stats,
+            solve: Stats::from_samples(&[ns(1000)]),
+        }];
+        let baseline = Baseline::from_benches(&benches);
+
+        assert_eq!(
+            baseline.to_toml(),
+            "[day9.part_2]\nparse_ns = 100\nsolve_ns = 1000\n\n"
+        );
+        assert_eq!(baseline.to_toml().parse::<Baseline>(), Ok(baseline.clone()));
+
+        let slower = Baseline::from_benches(&[Bench {
+            solve: Stats::from_samples(&[ns(500)]),
+            ..benches[0].clone()
+        }]);
+        let rendered = render(&benches, Some(&slower));
+
+        assert!(rendered.contains("parse: min 100ns, median 100ns, mean 100ns, stddev 0ns | baseline median 100ns (+0.0%)\n"));
+        assert!(rendered.contains("baseline median 500ns (+100.0% SLOWER)\n"));
+    }
+}
@@ -0,0 +1,539 @@
+use std::collections::BTreeSet;
+use std::fmt;
+
+use aoc_2020::answers::DEFAULT_ANSWERS_FILE;
+use aoc_2020::report::Format;
+
+pub const USAGE: &str =
+    "Usage: aoc_2020 [--all | --day <DAYS>] [--part <1|2>] [--input <FILE> | --inputs-dir <DIR>]
+                [--format <text|json|csv>] [--verify [--answers <FILE>]]
+                [--bench <N> [--baseline <FILE>] [--save-baseline <FILE>]] [--jobs <N>]
+                [--audit | --passports] [--schema <FILE>]
+
+Options:
+    -a, --all              Run every implemented day (the default)
+    -d, --day <DAYS>       Day(s) to run, e.g. `7`, `3-5` or `1,4,9-11`. Repeatable.
+    -p, --part <1|2>       Only run the given part
+    -i, --input <FILE>     Puzzle input for a single day, `-` reads stdin
+        --inputs-dir <DIR> Directory holding `day<N>.txt` files.
+                           Defaults to $AOC_INPUTS, then `./src`
+    -f, --format <FORMAT>  Report as `text` (the default), `json` or `csv`
+        --verify           Check answers against the known answers file and
+                           fail on any mismatch
+        --answers <FILE>   Known answers for --verify. Defaults to `answers.toml`
+        --bench <N>        Run each part N times and report parse and solve
+                           timings instead of answers
+        --baseline <FILE>  Compare --bench medians against a saved baseline
+        --save-baseline <FILE>
+                           Save --bench medians as a new baseline
+    -j, --jobs <N>         Run up to N parts at once. Output order doesn't change
+        --audit            Report day 2 password failures grouped by policy and
+                           cause. With `--format csv`, list the failing lines
+        --passports        Report which day 4 passport fields are missing,
+                           invalid or unknown. Also available as `--format json`
+        --schema <FILE>    Day 4 passport rules, in place of the built-in ones
+    -h, --help             Print this message";
+
+#[derive(Debug, PartialEq)]
+pub enum CliError {
+    Help,
+    MissingValue(String),
+    UnknownFlag(String),
+    InvalidDay(String),
+    UnknownDay(u16, Vec<u16>),
+    InvalidPart(String),
+    InvalidFormat(String),
+    InvalidIterations(String),
+    InvalidJobs(String),
+    Conflict(&'static str, &'static str),
+    InputNeedsSingleDay,
+    AuditNeedsDay2,
+    PassportsNeedDay4,
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CliError::Help => write!(f, "{}", USAGE),
+            CliError::MissingValue(flag) => write!(f, "{} expects a value", flag),
+            CliError::UnknownFlag(flag) => write!(f, "unknown argument [{}]", flag),
+            CliError::InvalidDay(raw) => write!(f, "[{}] isn't a valid day or day range", raw),
+            CliError::UnknownDay(day, available) => write!(
+                f,
+                "day {} isn't implemented (available: {})",
+                day,
+                available
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+            CliError::InvalidPart(raw) => {
+                write!(f, "[{}] isn't a valid part, expected 1 or 2", raw)
+            }
+            CliError::InvalidFormat(reason) => write!(f, "{}", reason),
+            CliError::InvalidIterations(raw) => {
+                write!(f, "[{}] isn't a valid number of iterations", raw)
+            }
+            CliError::InvalidJobs(raw) => write!(f, "[{}] isn't a valid number of jobs", raw),
+            CliError::Conflict(a, b) => write!(f, "{} can't be combined with {}", a, b),
+            CliError::InputNeedsSingleDay => write!(f, "--input needs exactly one --day"),
+            CliError::AuditNeedsDay2 => write!(f, "--audit only works on day 2"),
+            CliError::PassportsNeedDay4 => write!(f, "--passports only works on day 4"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Options {
+    pub days: Vec<u16>,
+    pub parts: Vec<u8>,
+    pub input: Option<String>,
+    pub inputs_dir: Option<String>,
+    pub format: Format,
+    // Path to the known answers, when verifying.
+    pub verify: Option<String>,
+    pub bench: Option<Bench>,
+    pub jobs: usize,
+    pub audit: bool,
+    pub passports: bool,
+    // Path to a day 4 passport schema.
+    pub schema: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Bench {
+    pub iterations: usize,
+    pub baseline: Option<String>,
+    pub save_baseline: Option<String>,
+}
+
+fn parse_positive(raw: &str, err: fn(String) -> CliError) -> Result<usize, CliError> {
+    match raw.parse::<usize>() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(err(raw.to_string())),
+    }
+}
+
+fn parse_day(raw: &str) -> Result<u16, CliError> {
+    raw.trim()
+        .parse::<u16>()
+        .map_err(|_| CliError::InvalidDay(raw.to_string()))
+}
+
+// Accepts `7`, `3-5` and comma separated combinations of the two.
+fn parse_days(raw: &str) -> Result<Vec<u16>, CliError> {
+    let mut days = vec![];
+
+    for chunk in raw.split(',') {
+        match chunk.split('-').collect::<Vec<_>>()[..] {
+            [single] => days.push(parse_day(single)?),
+            [from, to] => {
+                let (from, to) = (parse_day(from)?, parse_day(to)?);
+                if from > to {
+                    return Err(CliError::InvalidDay(chunk.to_string()));
+                }
+                days.extend(from..=to);
+            }
+            _ => return Err(CliError::InvalidDay(chunk.to_string())),
+        }
+    }
+
+    Ok(days)
+}
+
+fn parse_part(raw: &str) -> Result<u8, CliError> {
+    match raw {
+        "1" => Ok(1),
+        "2" => Ok(2),
+        x => Err(CliError::InvalidPart(x.to_string())),
+    }
+}
+
+pub fn parse_args<I>(args: I, available: &[u16]) -> Result<Options, CliError>
+where
+    I: IntoIterator<Item = String>,
+{
+    let mut args = args.into_iter();
+    let mut all = false;
+    let mut days = BTreeSet::new();
+    let mut part = None;
+    let mut input = None;
+    let mut inputs_dir = None;
+    let mut format = Format::Text;
+    let mut verify = false;
+    let mut answers = None;
+    let mut iterations = None;
+    let mut baseline = None;
+    let mut save_baseline = None;
+    let mut jobs = 1;
+    let mut audit = false;
+    let mut passports = false;
+    let mut schema = None;
+
+    while let Some(arg) = args.next() {
+        // Support both `--day 7` and `--day=7`
+        let (flag, inline) = match arg.find('=') {
+            Some(i) if arg.starts_with("--") => {
+                (arg[..i].to_string(), Some(arg[i + 1..].to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| CliError::MissingValue(flag.clone()))
+        };
+
+        match flag.as_str() {
+            "-h" | "--help" => return Err(CliError::Help),
+            "-a" | "--all" => all = true,
+            "-d" | "--day" => days.extend(parse_days(&value()?)?),
+            "-p" | "--part" => part = Some(parse_part(&value()?)?),
+            "-i" | "--input" => input = Some(value()?),
+            "--inputs-dir" => inputs_dir = Some(value()?),
+            "-f" | "--format" => format = value()?.parse().map_err(CliError::InvalidFormat)?,
+            "--verify" => verify = true,
+            "--answers" => answers = Some(value()?),
+            "--bench" => iterations = Some(parse_positive(&value()?, CliError::InvalidIterations)?),
+            "-j" | "--jobs" => jobs = parse_positive(&value()?, CliError::InvalidJobs)?,
+            "--baseline" => baseline = Some(value()?),
+            "--save-baseline" => save_baseline = Some(value()?),
+            "--audit" => audit = true,
+            "--passports" => passports = true,
+            "--schema" => schema = Some(value()?),
+            _ => return Err(CliError::UnknownFlag(arg)),
+        }
+    }
+
+    if all && !days.is_empty() {
+        return Err(CliError::Conflict("--all", "--day"));
+    }
+
+    if answers.is_some() && !verify {
+        return Err(CliError::Conflict("--answers", "running without --verify"));
+    }
+
+    if iterations.is_none() && baseline.is_some() {
+        return Err(CliError::Conflict("--baseline", "running without --bench"));
+    }
+
+    if iterations.is_none() && save_baseline.is_some() {
+        return Err(CliError::Conflict(
+            "--save-baseline",
+            "running without --bench",
+        ));
+    }
+
+    if iterations.is_some() && verify {
+        return Err(CliError::Conflict("--bench", "--verify"));
+    }
+
+    // Benchmarks running side by side would skew each other's timings.
+    if iterations.is_some() && jobs > 1 {
+        return Err(CliError::Conflict("--bench", "--jobs"));
+    }
+
+    if audit && iterations.is_some() {
+        return Err(CliError::Conflict("--audit", "--bench"));
+    }
+
+    if audit && verify {
+        return Err(CliError::Conflict("--audit", "--verify"));
+    }
+
+    if audit && format == Format::Json {
+        return Err(CliError::Conflict("--audit", "--format json"));
+    }
+
+    if audit && (all || days.iter().any(|&d| d != 2)) {
+        return Err(CliError::AuditNeedsDay2);
+    }
+
+    // Audits only cover day 2, so it can be left out.
+    if audit {
+        days.insert(2);
+    }
+
+    if passports && audit {
+        return Err(CliError::Conflict("--passports", "--audit"));
+    }
+
+    if passports && iterations.is_some() {
+        return Err(CliError::Conflict("--passports", "--bench"));
+    }
+
+    if passports && verify {
+        return Err(CliError::Conflict("--passports", "--verify"));
+    }
+
+    if passports && format == Format::Csv {
+        return Err(CliError::Conflict("--passports", "--format csv"));
+    }
+
+    if passports && (all || days.iter().any(|&d| d != 4)) {
+        return Err(CliError::PassportsNeedDay4);
+    }
+
+    if passports {
+        days.insert(4);
+    }
+
+    if input.is_some() && inputs_dir.is_some() {
+        return Err(CliError::Conflict("--input", "--inputs-dir"));
+    }
+
+    if input.is_some() && days.len() != 1 {
+        return Err(CliError::InputNeedsSingleDay);
+    }
+
+    if let Some(&unknown) = days.iter().find(|d| !available.contains(d)) {
+        return Err(CliError::UnknownDay(unknown, available.to_vec()));
+    }
+
+    let days = if days.is_empty() {
+        available.to_vec()
+    } else {
+        days.into_iter().collect()
+    };
+
+    if schema.is_some() && !days.contains(&4) {
+        return Err(CliError::Conflict("--schema", "running without day 4"));
+    }
+
+    Ok(Options {
+        days,
+        parts: part.map_or_else(|| vec![1, 2], |p| vec![p]),
+        input,
+        inputs_dir,
+        format,
+        verify: if verify {
+            Some(answers.unwrap_or_else(|| DEFAULT_ANSWERS_FILE.to_string()))
+        } else {
+            None
+        },
+        bench: iterations.map(|iterations| Bench {
+            iterations,
+            baseline,
+            save_baseline,
+        }),
+        jobs,
+        audit,
+        passports,
+        schema,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AVAILABLE: [u16; 11] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11];
+
+    fn parse(args: &[&str]) -> Result<Options, CliError> {
+        parse_args(args.iter().map(|s| s.to_string()), &AVAILABLE)
+    }
+
+    #[test]
+    fn defaults_to_everything() {
+        let options = parse(&[]).unwrap();
+
+        assert_eq!(options.days, AVAILABLE.to_vec());
+        assert_eq!(options.parts, vec![1, 2]);
+        assert_eq!(options.format, Format::Text);
+        assert_eq!(parse(&["--all"]).unwrap(), options);
+    }
+
+    #[test]
+    fn single_day_and_part() {
+        let options = parse(&["--day", "7", "--part", "2"]).unwrap();
+
+        assert_eq!(options.days, vec![7]);
+        assert_eq!(options.parts, vec![2]);
+        assert_eq!(parse(&["--day=7", "--part=2"]).unwrap(), options);
+    }
+
+    #[test]
+    fn day_ranges() {
+        let options = parse(&["--day", "9-11,1", "-d", "3"]).unwrap();
+
+        assert_eq!(options.days, vec![1, 3, 9, 10, 11]);
+        assert_eq!(
+            parse(&["--day", "5-3"]),
+            Err(CliError::InvalidDay("5-3".to_string()))
+        );
+    }
+
+    #[test]
+    fn inputs() {
+        let options = parse(&["--day", "3", "--input", "-"]).unwrap();
+
+        assert_eq!(options.input, Some("-".to_string()));
+        assert_eq!(
+            parse(&["--inputs-dir", "inputs"]).unwrap().inputs_dir,
+            Some("inputs".to_string())
+        );
+        assert_eq!(
+            parse(&["--day", "3-4", "--input", "day3.txt"]),
+            Err(CliError::InputNeedsSingleDay)
+        );
+        assert_eq!(
+            parse(&["-d", "3", "-i", "day3.txt", "--inputs-dir", "inputs"]),
+            Err(CliError::Conflict("--input", "--inputs-dir"))
+        );
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(parse(&["-f", "json"]).unwrap().format, Format::Json);
+        assert_eq!(parse(&["--format=csv"]).unwrap().format, Format::Csv);
+        assert!(matches!(
+            parse(&["--format", "xml"]),
+            Err(CliError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn verify() {
+        assert_eq!(parse(&[]).unwrap().verify, None);
+        assert_eq!(
+            parse(&["--verify"]).unwrap().verify,
+            Some("answers.toml".to_string())
+        );
+        assert_eq!(
+            parse(&["--verify", "--answers", "mine.toml"])
+                .unwrap()
+                .verify,
+            Some("mine.toml".to_string())
+        );
+        assert_eq!(
+            parse(&["--answers", "mine.toml"]),
+            Err(CliError::Conflict("--answers", "running without --verify"))
+        );
+    }
+
+    #[test]
+    fn bench() {
+        assert_eq!(parse(&[]).unwrap().bench, None);
+        assert_eq!(
+            parse(&["--bench", "20", "--baseline", "before.toml"])
+                .unwrap()
+                .bench,
+            Some(Bench {
+                iterations: 20,
+                baseline: Some("before.toml".to_string()),
+                save_baseline: None,
+            })
+        );
+        assert_eq!(
+            parse(&["--bench", "0"]),
+            Err(CliError::InvalidIterations("0".to_string()))
+        );
+        assert_eq!(
+            parse(&["--save-baseline", "after.toml"]),
+            Err(CliError::Conflict(
+                "--save-baseline",
+                "running without --bench"
+            ))
+        );
+        assert_eq!(
+            parse(&["--bench", "3", "--verify"]),
+            Err(CliError::Conflict("--bench", "--verify"))
+        );
+    }
+
+    #[test]
+    fn jobs() {
+        assert_eq!(parse(&[]).unwrap().jobs, 1);
+        assert_eq!(parse(&["-j", "4"]).unwrap().jobs, 4);
+        assert_eq!(
+            parse(&["--jobs", "0"]),
+            Err(CliError::InvalidJobs("0".to_string()))
+        );
+        assert_eq!(
+            parse(&["--jobs", "2", "--bench", "5"]),
+            Err(CliError::Conflict("--bench", "--jobs"))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            parse(&["--day", "12"]),
+            Err(CliError::UnknownDay(12, AVAILABLE.to_vec()))
+        );
+        assert_eq!(
+            parse(&["--day", "x"]),
+            Err(CliError::InvalidDay("x".to_string()))
+        );
+        assert_eq!(
+            parse(&["--part", "3"]),
+            Err(CliError::InvalidPart("3".to_string()))
+        );
+        assert_eq!(
+            parse(&["--day"]),
+            Err(CliError::MissingValue("--day".to_string()))
+        );
+        assert_eq!(
+            parse(&["--all", "--day", "1"]),
+            Err(CliError::Conflict("--all", "--day"))
+        );
+    }
+
+    #[test]
+    fn audit() {
+        let options = parse(&["--audit", "-p", "1"]).unwrap();
+
+        assert!(options.audit);
+        assert_eq!(options.days, vec![2]);
+        assert_eq!(parse(&["--audit", "-d", "2", "-p", "1"]).unwrap(), options);
+        assert_eq!(
+            parse(&["--audit", "-d", "1-2"]),
+            Err(CliError::AuditNeedsDay2)
+        );
+        assert_eq!(
+            parse(&["--audit", "-f", "json"]),
+            Err(CliError::Conflict("--audit", "--format json"))
+        );
+        assert_eq!(
+            parse(&["--audit", "--verify"]),
+            Err(CliError::Conflict("--audit", "--verify"))
+        );
+    }
+
+    #[test]
+    fn schema() {
+        assert_eq!(parse(&[]).unwrap().schema, None);
+        assert_eq!(
+            parse(&["-d", "4", "--schema=strict.toml"]).unwrap().schema,
+            Some("strict.toml".to_string())
+        );
+        assert!(parse(&["--schema", "strict.toml"]).is_ok());
+        assert!(parse(&["-d", "3-5", "--schema", "strict.toml"]).is_ok());
+        assert_eq!(
+            parse(&["-d", "1", "--schema", "strict.toml"]),
+            Err(CliError::Conflict("--schema", "running without day 4"))
+        );
+    }
+
+    #[test]
+    fn passports() {
+        let options = parse(&["--passports", "-f", "json"]).unwrap();
+
+        assert!(options.passports);
+        assert_eq!(options.days, vec![4]);
+        assert_eq!(
+            parse(&["--passports", "--day", "2"]),
+            Err(CliError::PassportsNeedDay4)
+        );
+        assert_eq!(
+            parse(&["--passports", "--audit"]),
+            Err(CliError::Conflict("--passports", "--audit"))
+        );
+        assert_eq!(
+            parse(&["--passports", "-f", "csv"]),
+            Err(CliError::Conflict("--passports", "--format csv"))
+        );
+    }
+}
@@ -1,67 +1,284 @@
-use std::fs;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::BufRead;
 
-pub const DAY: u16 = 1;
+use crate::error::{first_column, Error, Result};
+use crate::solution::Solution;
 
-fn parse_str(raw: &str) -> Vec<u64> {
-    let text = raw.split_ascii_whitespace();
+pub struct Day1;
 
-    text.into_iter()
-        .map(|l| l.parse::<u64>().unwrap())
-        .collect()
+const TARGET: u64 = 2020;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Solutions {
+    First,
+    // Every distinct combination of values, so duplicate entries don't repeat a match.
+    All,
 }
 
-fn parse() -> Vec<u64> {
-    let raw = fs::read_to_string("./src/day1.txt").unwrap();
-    parse_str(&raw)
+fn parse_str(raw: &str) -> Result<Vec<u64>> {
+    raw.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            l.trim().parse::<u64>().map_err(|e| {
+                Error::parse(Day1::DAY, l.trim(), e.to_string()).at(i + 1, first_column(l))
+            })
+        })
+        .collect()
 }
 
-fn find_pair(candidates: Vec<u64>) -> Option<(u64, u64)> {
-    for i in &candidates {
-        for j in &candidates {
-            if i + j == 2020 {
-                return Some((*i, *j));
-            }
+// Single pass, remembering what's been seen so far. O(n) but only finds one pair.
+fn two_sum_hash(values: &[u64], target: u64) -> Option<Vec<usize>> {
+    let mut seen = HashMap::new();
+
+    for (index, &value) in values.iter().enumerate() {
+        if let Some(&other) = target.checked_sub(value).and_then(|rest| seen.get(&rest)) {
+            return Some(vec![other, index]);
         }
+        seen.entry(value).or_insert(index);
     }
 
     None
 }
 
-fn find_triple(candidates: Vec<u64>) -> Option<(u64, u64, u64)> {
-    for i in &candidates {
-        for j in &candidates {
-            for k in &candidates {
-                if i + j + k == 2020 {
-                    return Some((*i, *j, *k));
+// `sorted` holds (value, original index) pairs in ascending value order.
+fn k_sum_sorted(
+    sorted: &[(u64, usize)],
+    k: usize,
+    target: u64,
+    solutions: Solutions,
+    chosen: &mut Vec<usize>,
+    found: &mut Vec<Vec<usize>>,
+) {
+    let done = |found: &Vec<Vec<usize>>| solutions == Solutions::First && !found.is_empty();
+
+    match k {
+        0 => {
+            if target == 0 {
+                found.push(chosen.clone());
+            }
+        }
+        1 => {
+            if let Ok(i) = sorted.binary_search_by_key(&target, |&(value, _)| value) {
+                found.push([&chosen[..], &[sorted[i].1]].concat());
+            }
+        }
+        2 => {
+            // Two pointers closing in from either end.
+            let (mut lo, mut hi) = (0, sorted.len().saturating_sub(1));
+            while lo < hi && !done(found) {
+                let (low, high) = (sorted[lo].0, sorted[hi].0);
+                match low.checked_add(high).map(|sum| sum.cmp(&target)) {
+                    Some(std::cmp::Ordering::Less) => lo += 1,
+                    Some(std::cmp::Ordering::Equal) => {
+                        found.push([&chosen[..], &[sorted[lo].1, sorted[hi].1]].concat());
+                        while lo < hi && sorted[lo].0 == low {
+                            lo += 1;
+                        }
+                        while lo < hi && sorted[hi].0 == high {
+                            hi -= 1;
+                        }
+                    }
+                    _ => hi -= 1,
+                }
+            }
+        }
+        _ => {
+            for i in 0..sorted.len() {
+                let (value, index) = sorted[i];
+                if value > target || done(found) {
+                    break;
+                }
+                if i > 0 && sorted[i - 1].0 == value {
+                    continue;
                 }
+
+                chosen.push(index);
+                k_sum_sorted(
+                    &sorted[i + 1..],
+                    k - 1,
+                    target - value,
+                    solutions,
+                    chosen,
+                    found,
+                );
+                chosen.pop();
             }
         }
     }
+}
 
-    None
+// Finds `k` entries summing to `target`, using each index at most once.
+// Every solution is a list of indices into `values`, in ascending order.
+pub fn find_k_sum(values: &[u64], k: usize, target: u64, solutions: Solutions) -> Vec<Vec<usize>> {
+    let mut found = if k == 2 && solutions == Solutions::First {
+        two_sum_hash(values, target).into_iter().collect()
+    } else {
+        let mut sorted = values
+            .iter()
+            .copied()
+            .enumerate()
+            .map(|(index, value)| (value, index))
+            .collect::<Vec<_>>();
+        sorted.sort_unstable();
+
+        let mut found = vec![];
+        k_sum_sorted(&sorted, k, target, solutions, &mut vec![], &mut found);
+        found
+    };
+
+    for indices in &mut found {
+        indices.sort_unstable();
+    }
+    found
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Entry {
+    pub index: usize,
+    pub value: u64,
 }
 
-pub fn part_1() {
-    let data = parse();
-    let pair = find_pair(data).unwrap();
-    let (i, j) = pair;
-    println!("{:?} multiplied together has the product {}", pair, i * j)
+#[derive(Clone, Debug, PartialEq)]
+pub enum Audit {
+    Found { entries: Vec<Entry>, product: u128 },
+    NoSolution { k: usize, target: u64 },
+    // The entries were found, but multiplying them doesn't fit in a u128.
+    ProductOverflow { entries: Vec<Entry> },
 }
 
-pub fn part_2() {
-    let data = parse();
-    let triple = find_triple(data).unwrap();
-    let (i, j, k) = triple;
+impl fmt::Display for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Audit::Found { product, .. } => write!(f, "{}", product),
+            Audit::NoSolution { k, target } => {
+                write!(f, "no solution: no {} entries sum to {}", k, target)
+            }
+            Audit::ProductOverflow { entries } => write!(
+                f,
+                "product overflow: {:?} multiplied together doesn't fit in a u128",
+                entries.iter().map(|e| e.value).collect::<Vec<_>>()
+            ),
+        }
+    }
+}
 
-    println!(
-        "{:?} multiplied together has the product {}",
-        triple,
-        i * j * k
+pub fn audit(values: &[u64], k: usize, target: u64) -> Audit {
+    let indices = match find_k_sum(values, k, target, Solutions::First)
+        .into_iter()
+        .next()
+    {
+        Some(indices) => indices,
+        None => return Audit::NoSolution { k, target },
+    };
+    audit_from(
+        indices
+            .into_iter()
+            .map(|index| Entry {
+                index,
+                value: values[index],
+            })
+            .collect(),
     )
 }
 
+fn audit_from(entries: Vec<Entry>) -> Audit {
+    match entries
+        .iter()
+        .try_fold(1u128, |product, e| product.checked_mul(e.value as u128))
+    {
+        Some(product) => Audit::Found { entries, product },
+        None => Audit::ProductOverflow { entries },
+    }
+}
+
+// Reads entries one line at a time and stops as soon as `k` of them (2 or 3)
+// sum to `target`, so the rest of the input is never read.
+pub fn stream_audit<R: BufRead>(reader: R, k: usize, target: u64) -> Result<Audit> {
+    if k != 2 && k != 3 {
+        return Err(Error::Unsupported {
+            day: Day1::DAY,
+            reason: format!("streaming only finds 2 or 3 entries, not {}", k),
+        });
+    }
+
+    // Up to two indices per value, enough to use a value twice in a triple.
+    let mut seen: HashMap<u64, Vec<usize>> = HashMap::new();
+    let mut values = vec![];
+
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line.map_err(|e| Error::Read {
+            day: Day1::DAY,
+            line: line_index + 1,
+            reason: e.to_string(),
+        })?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let value = line.trim().parse::<u64>().map_err(|e| {
+            Error::parse(Day1::DAY, line.trim(), e.to_string())
+                .at(line_index + 1, first_column(&line))
+        })?;
+        let index = values.len();
+        let entry = |i: usize| Entry {
+            index: i,
+            value: values[i],
+        };
+
+        if let Some(rest) = target.checked_sub(value) {
+            if k == 2 {
+                if let Some(&other) = seen.get(&rest).and_then(|indices| indices.first()) {
+                    return Ok(audit_from(vec![entry(other), Entry { index, value }]));
+                }
+            } else {
+                for (i, &first) in values.iter().enumerate() {
+                    let second = rest
+                        .checked_sub(first)
+                        .and_then(|wanted| seen.get(&wanted))
+                        .and_then(|indices| indices.iter().find(|&&j| j != i));
+                    if let Some(&j) = second {
+                        let (i, j) = (i.min(j), i.max(j));
+                        return Ok(audit_from(vec![entry(i), entry(j), Entry { index, value }]));
+                    }
+                }
+            }
+        }
+
+        let indices = seen.entry(value).or_default();
+        if indices.len() < 2 {
+            indices.push(index);
+        }
+        values.push(value);
+    }
+
+    Ok(Audit::NoSolution { k, target })
+}
+
+impl Solution for Day1 {
+    const DAY: u16 = 1;
+
+    type Input = Vec<u64>;
+    type Part1 = Audit;
+    type Part2 = Audit;
+
+    fn parse(raw: &str) -> Result<Self::Input> {
+        parse_str(raw)
+    }
+
+    fn part_1(data: &Self::Input) -> Result<Self::Part1> {
+        Ok(audit(data, 2, TARGET))
+    }
+
+    fn part_2(data: &Self::Input) -> Result<Self::Part2> {
+        Ok(audit(data, 3, TARGET))
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{Audit, Entry};
+
     const RAW_DATA: &str = "1721
         979
         366
@@ -71,15 +288,189 @@ mod tests {
 
     #[test]
     fn find_pair_works() {
-        let data = super::parse_str(RAW_DATA);
-        let res = super::find_pair(data);
-        assert_eq!(Some((1721, 299)), res);
+        let data = super::parse_str(RAW_DATA).unwrap();
+        let res = super::audit(&data, 2, 2020);
+        assert_eq!(
+            Audit::Found {
+                entries: vec![
+                    Entry {
+                        index: 0,
+                        value: 1721
+                    },
+                    Entry {
+                        index: 3,
+                        value: 299
+                    }
+                ],
+                product: 514579
+            },
+            res
+        );
     }
 
     #[test]
     fn find_triple_works() {
-        let data = super::parse_str(RAW_DATA);
-        let res = super::find_triple(data);
-        assert_eq!(Some((979, 366, 675)), res);
+        let data = super::parse_str(RAW_DATA).unwrap();
+        let res = super::audit(&data, 3, 2020);
+        match res {
+            Audit::Found { entries, product } => {
+                assert_eq!(
+                    vec![979, 366, 675],
+                    entries.iter().map(|e| e.value).collect::<Vec<_>>()
+                );
+                assert_eq!(product, 241861950);
+            }
+            x => panic!("Invalid variant {:?}", x),
+        }
+    }
+
+    #[test]
+    fn audit_outcomes() {
+        let res = super::audit(&[1, 2, 3], 2, 2020);
+        assert_eq!(res, Audit::NoSolution { k: 2, target: 2020 });
+        assert_eq!(res.to_string(), "no solution: no 2 entries sum to 2020");
+
+        let big = u64::MAX / 4;
+        let res = super::audit(&[big, big, big, 3], 3, big * 3);
+        assert!(matches!(res, Audit::ProductOverflow { .. }));
+        assert!(res.to_string().starts_with("product overflow"));
+
+        // Two u64s always fit
+        let res = super::audit(&[u64::MAX - 1, 1], 2, u64::MAX);
+        assert_eq!(res.to_string(), (u64::MAX as u128 - 1).to_string());
+    }
+
+    #[test]
+    fn find_k_sum_uses_each_entry_once() {
+        use super::{find_k_sum, Solutions};
+
+        assert!(find_k_sum(&[1010, 5], 2, 2020, Solutions::First).is_empty());
+        assert!(find_k_sum(&[1010, 5], 2, 2020, Solutions::All).is_empty());
+        assert_eq!(
+            find_k_sum(&[5, 1010, 1010], 2, 2020, Solutions::First),
+            vec![vec![1, 2]]
+        );
+        assert!(find_k_sum(&[674, 672, 5], 3, 2020, Solutions::First).is_empty());
+    }
+
+    #[test]
+    fn find_k_sum_all() {
+        use super::{find_k_sum, Solutions};
+
+        let values = [1, 9, 5, 5, 2, 8, 10, 0];
+        let mut pairs = find_k_sum(&values, 2, 10, Solutions::All);
+        pairs.sort();
+        // The two 5s are separate entries, so they can pair up with each other
+        assert_eq!(pairs, vec![vec![0, 1], vec![2, 3], vec![4, 5], vec![6, 7]]);
+
+        let mut quads = find_k_sum(&values, 4, 20, Solutions::All)
+            .into_iter()
+            .map(|indices| {
+                let mut quad = indices.iter().map(|&i| values[i]).collect::<Vec<_>>();
+                quad.sort_unstable();
+                quad
+            })
+            .collect::<Vec<_>>();
+        quads.sort();
+        assert_eq!(
+            quads,
+            vec![
+                vec![0, 1, 9, 10],
+                vec![0, 2, 8, 10],
+                vec![0, 5, 5, 10],
+                vec![1, 2, 8, 9],
+                vec![1, 5, 5, 9],
+                vec![2, 5, 5, 8],
+            ]
+        );
+
+        assert_eq!(find_k_sum(&values, 0, 0, Solutions::All), vec![vec![]]);
+        assert_eq!(find_k_sum(&values, 1, 8, Solutions::All), vec![vec![5]]);
+    }
+
+    #[test]
+    fn find_k_sum_large_values() {
+        use super::{find_k_sum, Solutions};
+
+        let values = [u64::MAX, 1, u64::MAX - 1];
+        assert_eq!(
+            find_k_sum(&values, 2, u64::MAX, Solutions::All),
+            vec![vec![1, 2]]
+        );
+        assert_eq!(
+            find_k_sum(&values, 2, u64::MAX, Solutions::First),
+            vec![vec![1, 2]]
+        );
+    }
+
+    #[test]
+    fn parse_error() {
+        let err = super::parse_str("1721\n  97x9").unwrap_err();
+
+        assert_eq!(
+            err,
+            super::Error::Parse {
+                day: 1,
+                line: 2,
+                column: 3,
+                text: "97x9".to_string(),
+                reason: "invalid digit found in string".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn stream_audit_stops_early() {
+        use std::io::{self, BufReader, Read};
+
+        // Blows up if anything past the answer gets read.
+        struct Broken;
+        impl Read for Broken {
+            fn read(&mut self, _: &mut [u8]) -> io::Result<usize> {
+                Err(io::Error::other("read too far"))
+            }
+        }
+
+        let reader = BufReader::new(RAW_DATA.as_bytes().chain(Broken));
+        let res = super::stream_audit(reader, 2, 2020).unwrap();
+        assert_eq!(res, super::audit(&[1721, 979, 366, 299], 2, 2020));
+
+        let reader = BufReader::new("979\n366\n\n675\n".as_bytes().chain(Broken));
+        let res = super::stream_audit(reader, 3, 2020).unwrap();
+        assert_eq!(res.to_string(), "241861950");
+
+        let reader = BufReader::new("1\n2\n".as_bytes().chain(Broken));
+        let err = super::stream_audit(reader, 2, 2020).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 1 input, line 3: couldn't read: read too far"
+        );
+
+        // Refused before anything is read.
+        let err = super::stream_audit(BufReader::new(Broken), 4, 2020).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 1: streaming only finds 2 or 3 entries, not 4"
+        );
+    }
+
+    #[test]
+    fn stream_audit_uses_each_entry_once() {
+        let res = super::stream_audit("1010\n5\n".as_bytes(), 2, 2020).unwrap();
+        assert_eq!(res, Audit::NoSolution { k: 2, target: 2020 });
+
+        let res = super::stream_audit("5\n1010\n1010\n".as_bytes(), 2, 2020).unwrap();
+        assert_eq!(res, super::audit(&[5, 1010, 1010], 2, 2020));
+
+        let res = super::stream_audit("2\n1009\n1009\n".as_bytes(), 3, 2020).unwrap();
+        assert_eq!(res.to_string(), (2 * 1009 * 1009).to_string());
+        let res = super::stream_audit("2\n1009\n".as_bytes(), 3, 2020).unwrap();
+        assert_eq!(res, Audit::NoSolution { k: 3, target: 2020 });
+
+        let err = super::stream_audit("1\nx\n".as_bytes(), 3, 2020).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 1 input, line 2 column 1: invalid digit found in string [x]"
+        );
     }
 }
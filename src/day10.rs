@@ -1,19 +1,29 @@
-use std::fs;
+use crate::error::{first_column, Error, Result};
+use crate::solution::Solution;
 
-pub const DAY: u16 = 10;
+pub struct Day10;
 
-fn parse_str(raw: &str) -> Vec<u32> {
+fn parse_str(raw: &str) -> Result<Vec<u32>> {
     let mut joltages = raw
         .lines()
-        .map(|l| l.parse::<u32>().unwrap())
-        .collect::<Vec<_>>();
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            l.trim().parse::<u32>().map_err(|e| {
+                Error::parse(Day10::DAY, l.trim(), e.to_string()).at(i + 1, first_column(l))
+            })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if joltages.is_empty() {
+        return Err(Error::Empty { day: Day10::DAY });
+    }
     joltages.push(0);
     joltages.push(joltages.iter().max().unwrap() + 3);
     joltages.sort_unstable();
-    joltages
+    Ok(joltages)
 }
 
-fn one_and_three_jolt_differences(joltages: Vec<u32>) -> (u32, u32) {
+fn one_and_three_jolt_differences(joltages: &[u32]) -> (u32, u32) {
     joltages
         .windows(2)
         .fold((0, 0), |(one_jump, three_jump), curr| match curr {
@@ -30,46 +40,44 @@ fn one_and_three_jolt_differences(joltages: Vec<u32>) -> (u32, u32) {
         })
 }
 
-pub fn part_1() {
-    let raw = fs::read_to_string("./src/day10.txt").unwrap();
-    let parsed = parse_str(&raw);
-    let (diff_1, diff_3) = one_and_three_jolt_differences(parsed);
+// `from` is sorted, so each adapter can only be reached from the (at most three)
+// adapters right before it. Sum up the ways of reaching those.
+fn count_possible_chains(from: &[u32]) -> u128 {
+    let mut ways: Vec<u128> = vec![0; from.len()];
+
+    for (index, &joltage) in from.iter().enumerate() {
+        ways[index] = if index == 0 {
+            1
+        } else {
+            (index.saturating_sub(3)..index)
+                .filter(|&prev| joltage - from[prev] <= 3)
+                .map(|prev| ways[prev])
+                .sum()
+        };
+    }
 
-    dbg!(diff_1, diff_3, diff_1 * diff_3);
+    ways.last().copied().unwrap_or(0)
 }
 
-fn count_possible_chains(from: Vec<u32>) -> u128 {
-    let diff_chain = from
-        .windows(2)
-        .filter_map(|window| match &window {
-            [x, y] if y - x < 3 => Some(y - x),
-            _ => None,
-        })
-        .collect::<Vec<_>>();
+impl Solution for Day10 {
+    const DAY: u16 = 10;
 
-    let chains = diff_chain
-        .iter()
-        .enumerate()
-        .fold(1, |count, (index, &curr)| {
-            match (curr, diff_chain.get(index + 1), diff_chain.get(index + 2)) {
-                (2, Some(2), _) => count,
-                (2, Some(1), Some(2)) | (1, Some(2), Some(2)) => count * 2,
-                (1, Some(1), Some(2)) | (1, Some(2), Some(1)) => count * 3,
-                x => panic!("{:?} not a valid pattern", x),
-            }
-        });
+    type Input = Vec<u32>;
+    type Part1 = u32;
+    type Part2 = u128;
 
-    chains
-}
-
-pub fn part_2() {
-    let raw = fs::read_to_string("./src/day10.txt").unwrap();
-    let _parsed = parse_str(&raw);
+    fn parse(raw: &str) -> Result<Self::Input> {
+        parse_str(raw)
+    }
 
-    count_possible_chains(vec![]);
+    fn part_1(parsed: &Self::Input) -> Result<Self::Part1> {
+        let (diff_1, diff_3) = one_and_three_jolt_differences(parsed);
+        Ok(diff_1 * diff_3)
+    }
 
-    // let result = count_possible_chains(parsed);
-    // dbg!(result);
+    fn part_2(parsed: &Self::Input) -> Result<Self::Part2> {
+        Ok(count_possible_chains(parsed))
+    }
 }
 
 #[cfg(test)]
@@ -126,24 +134,38 @@ mod tests {
 
     #[test]
     fn first_not_sum_of_preable() {
-        let parsed = super::parse_str(RAW_DATA);
-        let result = super::one_and_three_jolt_differences(parsed);
+        let parsed = super::parse_str(RAW_DATA).unwrap();
+        let result = super::one_and_three_jolt_differences(&parsed);
 
         assert_eq!(result, (7, 5))
     }
 
     #[test]
     fn count_possible_chains() {
-        let parsed = super::parse_str(RAW_DATA);
-        let result = super::count_possible_chains(parsed);
+        let parsed = super::parse_str(RAW_DATA).unwrap();
+        let result = super::count_possible_chains(&parsed);
 
         assert_eq!(result, 8);
     }
 
     #[test]
     fn count_possible_chains_bigger() {
-        let parsed = super::parse_str(LARGER_EXAMPLE);
-        let result = super::count_possible_chains(parsed);
+        let parsed = super::parse_str(LARGER_EXAMPLE).unwrap();
+        let result = super::count_possible_chains(&parsed);
         assert_eq!(result, 19208)
     }
+
+    #[test]
+    fn blank_lines() {
+        let trailing = format!("{}\n\n", RAW_DATA);
+        assert_eq!(super::parse_str(&trailing), super::parse_str(RAW_DATA));
+        assert_eq!(
+            super::parse_str("1\n\nx").unwrap_err().to_string(),
+            "day 10 input, line 3 column 1: invalid digit found in string [x]"
+        );
+        assert_eq!(
+            super::parse_str("\n"),
+            Err(crate::error::Error::Empty { day: 10 })
+        );
+    }
 }
@@ -1,112 +1,774 @@
 use counter::Counter;
 use regex::Regex;
-use std::fs;
-use std::num::ParseIntError;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write};
 use std::str::FromStr;
-pub const DAY: u16 = 2;
+
+use crate::error::{first_column, Error, Result};
+use crate::report::csv_field;
+use crate::solution::Solution;
+
+pub struct Day2;
+
+// What a policy looked at when it made its call.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Observed {
+    Count(usize),
+    // The checked 1-based positions where the token starts.
+    Positions(Vec<usize>),
+    Length(usize),
+    MissingClasses(Vec<CharClass>),
+    ForbiddenFound(Vec<String>),
+    Pattern,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Verdict {
+    pub pass: bool,
+    // Short and fixed per kind of failure, so audits can group on it.
+    pub cause: &'static str,
+    pub observed: Observed,
+    pub reason: String,
+}
+
+impl Verdict {
+    fn new(pass: bool, cause: &'static str, observed: Observed, reason: String) -> Verdict {
+        Verdict {
+            pass,
+            cause: if pass { "ok" } else { cause },
+            observed,
+            reason,
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let outcome = if self.pass { "pass" } else { "fail" };
+        write!(f, "{}: {}", outcome, self.reason)
+    }
+}
+
+// Display should print the policy the way it's tagged in the input.
+pub trait PasswordPolicy: fmt::Debug + fmt::Display {
+    // The tag this policy goes by in the input.
+    fn kind(&self) -> &'static str;
+
+    fn evaluate(&self, password: &str) -> Verdict;
+
+    // The token the policy is about, if it's about one.
+    fn token(&self) -> Option<&str> {
+        None
+    }
+
+    fn is_valid(&self, password: &str) -> bool {
+        self.evaluate(password).pass
+    }
+}
+
+// 1-based character positions where `token` starts. Occurrences may overlap,
+// so `aa` shows up twice in `aaa`.
+fn occurrences(password: &str, token: &str) -> Vec<usize> {
+    password
+        .char_indices()
+        .enumerate()
+        .filter(|(_, (byte, _))| password[*byte..].starts_with(token))
+        .map(|(position, _)| position + 1)
+        .collect()
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CountRange {
+    pub lower: usize,
+    pub upper: usize,
+    pub token: String,
+}
+
+// The token shows up between `lower` and `upper` times, inclusive.
+impl PasswordPolicy for CountRange {
+    fn kind(&self) -> &'static str {
+        "count"
+    }
+
+    fn token(&self) -> Option<&str> {
+        Some(&self.token)
+    }
+
+    fn evaluate(&self, password: &str) -> Verdict {
+        let count = occurrences(password, &self.token).len();
+        let pass = count >= self.lower && self.upper >= count;
+        let reason = format!(
+            "count of {:?} is {}, {} {}-{}",
+            self.token,
+            count,
+            if pass { "within" } else { "expected" },
+            self.lower,
+            self.upper
+        );
+
+        let cause = if count < self.lower {
+            "too few"
+        } else {
+            "too many"
+        };
+
+        Verdict::new(pass, cause, Observed::Count(count), reason)
+    }
+}
+
+impl fmt::Display for CountRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "count {}-{} {}", self.lower, self.upper, self.token)
+    }
+}
+
+// The token starts at exactly one of the two 1-based positions.
+#[derive(Debug, PartialEq)]
+pub struct Positions {
+    pub first: usize,
+    pub second: usize,
+    pub token: String,
+}
+
+impl PasswordPolicy for Positions {
+    fn kind(&self) -> &'static str {
+        "positions"
+    }
+
+    fn token(&self) -> Option<&str> {
+        Some(&self.token)
+    }
+
+    fn evaluate(&self, password: &str) -> Verdict {
+        let starts = occurrences(password, &self.token);
+        let found = [self.first, self.second]
+            .iter()
+            .copied()
+            .filter(|p| starts.contains(p))
+            .collect::<Vec<_>>();
+        let reason = match found.as_slice() {
+            [] => format!(
+                "{:?} is at neither position {} nor {}",
+                self.token, self.first, self.second
+            ),
+            [p] => format!("{:?} is at position {} only", self.token, p),
+            _ => format!(
+                "{:?} is at both positions {} and {}",
+                self.token, self.first, self.second
+            ),
+        };
+
+        let cause = if found.is_empty() {
+            "at neither position"
+        } else {
+            "at both positions"
+        };
+
+        Verdict::new(found.len() == 1, cause, Observed::Positions(found), reason)
+    }
+}
+
+impl fmt::Display for Positions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "positions {}-{} {}", self.first, self.second, self.token)
+    }
+}
+
+#[derive(Debug)]
+pub struct Pattern(pub Regex);
+
+impl PasswordPolicy for Pattern {
+    fn kind(&self) -> &'static str {
+        "regex"
+    }
+
+    fn evaluate(&self, password: &str) -> Verdict {
+        let pass = self.0.is_match(password);
+        let reason = format!(
+            "{} /{}/",
+            if pass { "matches" } else { "doesn't match" },
+            self.0
+        );
+
+        Verdict::new(pass, "no match", Observed::Pattern, reason)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "regex {}", self.0)
+    }
+}
+
+// Length in characters, inclusive on both ends.
+#[derive(Debug, PartialEq)]
+pub struct Length {
+    pub min: usize,
+    pub max: usize,
+}
+
+impl PasswordPolicy for Length {
+    fn kind(&self) -> &'static str {
+        "length"
+    }
+
+    fn evaluate(&self, password: &str) -> Verdict {
+        let length = password.chars().count();
+        let pass = (self.min..=self.max).contains(&length);
+        let reason = format!(
+            "{} characters long, {} {}-{}",
+            length,
+            if pass { "within" } else { "expected" },
+            self.min,
+            self.max
+        );
+
+        let cause = if length < self.min {
+            "too short"
+        } else {
+            "too long"
+        };
+
+        Verdict::new(pass, cause, Observed::Length(length), reason)
+    }
+}
+
+impl fmt::Display for Length {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "length {}-{}", self.min, self.max)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CharClass {
+    Lower,
+    Upper,
+    Digit,
+    Symbol,
+}
+
+impl CharClass {
+    pub fn matches(self, c: char) -> bool {
+        match self {
+            CharClass::Lower => c.is_lowercase(),
+            CharClass::Upper => c.is_uppercase(),
+            CharClass::Digit => c.is_numeric(),
+            CharClass::Symbol => !c.is_alphanumeric() && !c.is_whitespace(),
+        }
+    }
+}
+
+impl FromStr for CharClass {
+    type Err = String;
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        match raw {
+            "lower" => Ok(CharClass::Lower),
+            "upper" => Ok(CharClass::Upper),
+            "digit" => Ok(CharClass::Digit),
+            "symbol" => Ok(CharClass::Symbol),
+            _ => Err("expected lower, upper, digit or symbol".to_string()),
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            CharClass::Lower => "lower",
+            CharClass::Upper => "upper",
+            CharClass::Digit => "digit",
+            CharClass::Symbol => "symbol",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+// Every class needs at least one character in the password.
+#[derive(Debug, PartialEq)]
+pub struct RequiredClasses(pub Vec<CharClass>);
+
+impl PasswordPolicy for RequiredClasses {
+    fn kind(&self) -> &'static str {
+        "classes"
+    }
+
+    fn evaluate(&self, password: &str) -> Verdict {
+        let missing = self
+            .0
+            .iter()
+            .copied()
+            .filter(|&class| !password.chars().any(|c| class.matches(c)))
+            .collect::<Vec<_>>();
+        let reason = if missing.is_empty() {
+            "has every required class".to_string()
+        } else {
+            let names = missing.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+            format!("missing {}", names.join(", "))
+        };
+
+        Verdict::new(
+            missing.is_empty(),
+            "missing classes",
+            Observed::MissingClasses(missing),
+            reason,
+        )
+    }
+}
+
+impl fmt::Display for RequiredClasses {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names = self.0.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        write!(f, "classes {}", names.join(","))
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Forbidden(pub Vec<String>);
+
+impl PasswordPolicy for Forbidden {
+    fn kind(&self) -> &'static str {
+        "forbidden"
+    }
+
+    fn evaluate(&self, password: &str) -> Verdict {
+        let found = self
+            .0
+            .iter()
+            .filter(|s| password.contains(s.as_str()))
+            .cloned()
+            .collect::<Vec<_>>();
+        let reason = if found.is_empty() {
+            "contains nothing forbidden".to_string()
+        } else {
+            format!("contains {}", found.join(", "))
+        };
+
+        Verdict::new(
+            found.is_empty(),
+            "forbidden substring",
+            Observed::ForbiddenFound(found),
+            reason,
+        )
+    }
+}
+
+impl fmt::Display for Forbidden {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "forbidden {}", self.0.join(","))
+    }
+}
 
 #[derive(Debug, PartialEq)]
 struct Policy {
     lower: usize,
     upper: usize,
-    character: char,
+    token: String,
+}
+
+impl Policy {
+    fn count_range(&self) -> CountRange {
+        CountRange {
+            lower: self.lower,
+            upper: self.upper,
+            token: self.token.clone(),
+        }
+    }
+
+    fn positions(&self) -> Positions {
+        Positions {
+            first: self.lower,
+            second: self.upper,
+            token: self.token.clone(),
+        }
+    }
 }
 
 #[derive(Debug, PartialEq)]
-struct PolicyAndPass {
+pub struct PolicyAndPass {
     policy: Policy,
     password: String,
 }
 
-impl PolicyAndPass {
-    fn is_password_valid(&self) -> bool {
-        let char_counts = self.password.chars().collect::<Counter<_>>();
+#[derive(Debug)]
+pub enum Policies {
+    // Untagged puzzle lines: part 1 reads them as a count range, part 2 as positions.
+    Toboggan(CountRange, Positions),
+    // Tagged lines apply the same policy in both parts.
+    Tagged(Box<dyn PasswordPolicy>),
+}
 
-        let &count = char_counts.get(&self.policy.character).unwrap_or(&0);
-        !(count >= self.policy.lower && self.policy.upper >= count)
+#[derive(Debug)]
+pub struct Entry {
+    pub policies: Policies,
+    pub password: String,
+}
+
+impl Entry {
+    pub fn policy(&self, part: u8) -> &dyn PasswordPolicy {
+        match (&self.policies, part) {
+            (Policies::Toboggan(count, _), 1) => count,
+            (Policies::Toboggan(_, positions), _) => positions,
+            (Policies::Tagged(policy), _) => policy.as_ref(),
+        }
     }
 
-    fn is_password_valid_ii(&self) -> bool {
-        let chars = self.password.chars().collect::<Vec<_>>();
-        let policy_char = Some(&self.policy.character);
-        match (
-            chars.get(self.policy.lower - 1) == policy_char,
-            (chars.get(self.policy.upper - 1)) == policy_char,
-        ) {
-            (true, false) => true,
-            (false, true) => true,
-            _ => false,
+    pub fn evaluate(&self, part: u8) -> Verdict {
+        self.policy(part).evaluate(&self.password)
+    }
+
+    pub fn is_valid(&self, part: u8) -> bool {
+        self.policy(part).is_valid(&self.password)
+    }
+}
+
+impl From<PolicyAndPass> for Entry {
+    fn from(pp: PolicyAndPass) -> Self {
+        Entry {
+            policies: Policies::Toboggan(pp.policy.count_range(), pp.policy.positions()),
+            password: pp.password,
         }
     }
 }
 
 impl FromStr for PolicyAndPass {
-    type Err = ParseIntError;
-    fn from_str(line: &str) -> Result<Self, Self::Err> {
+    type Err = Error;
+    fn from_str(line: &str) -> Result<Self> {
         lazy_static! {
             static ref RE: Regex = Regex::new(
                 r"(?x)
-            (?P<lower>\d+) # lower bound of repetitions
+            ^(?P<lower>\d+) # lower bound of repetitions
             -
             (?P<upper>\d+) # upper bound of repetitions
-            \s
-            (?P<letters>\w) # letter(s?)
-            :\s
-            (?P<password>\w+)$
+            \x20
+            (?P<token>\S+?) # one or more characters
+            :\x20
+            (?P<password>.*)$ # anything, including spaces
             ",
             )
             .unwrap();
         }
 
-        let cap = RE.captures(line).unwrap();
-        let lower = &cap[1];
-        let lower = lower.parse::<usize>()?;
-        let upper = &cap[2];
-        let upper = upper.parse::<usize>()?;
-        let raw_letters = &cap[3];
+        let cap = RE.captures(line).ok_or_else(|| {
+            Error::parse(
+                Day2::DAY,
+                line,
+                "expected `<lower>-<upper> <token>: <password>`",
+            )
+        })?;
+        let bound = |i: usize| {
+            let m = cap.get(i).unwrap();
+            m.as_str().parse::<usize>().map_err(|e| {
+                Error::parse(Day2::DAY, m.as_str(), e.to_string()).at(1, m.start() + 1)
+            })
+        };
+        let lower = bound(1)?;
+        let upper = bound(2)?;
+        let token = cap[3].to_string();
         let password = cap[4].to_string();
 
         Ok(PolicyAndPass {
             policy: Policy {
                 lower,
                 upper,
-                character: raw_letters.chars().next().unwrap(),
+                token,
             },
             password,
         })
     }
 }
 
-fn parse_str(s: &str) -> Vec<PolicyAndPass> {
-    let text = s.lines();
+// Parses `<min>-<max>` where both ends are positive integers and `min` is
+// at most `max`.
+fn parse_range(raw: &str) -> Result<(usize, usize)> {
+    let (min, max) = raw
+        .split_once('-')
+        .ok_or_else(|| Error::parse(Day2::DAY, raw, "expected `<min>-<max>`"))?;
+    let bound = |s: &str, column: usize| {
+        s.parse::<usize>()
+            .map_err(|e| Error::parse(Day2::DAY, s, e.to_string()).at(1, column))
+    };
+
+    let (min, max) = (bound(min, 1)?, bound(max, min.len() + 2)?);
+
+    if min > max {
+        return Err(Error::parse(
+            Day2::DAY,
+            raw,
+            format!("{} is more than {}, nothing would pass", min, max),
+        ));
+    }
+
+    Ok((min, max))
+}
+
+// Errors are positioned relative to the spec. Unknown tags give `None`.
+fn parse_policy(tag: &str, spec: &str) -> Result<Option<Box<dyn PasswordPolicy>>> {
+    let policy: Box<dyn PasswordPolicy> = match tag {
+        "count" | "positions" => {
+            // Reuse the puzzle syntax, with a dummy password.
+            let pp = format!("{}: x", spec).parse::<PolicyAndPass>()?;
+            if tag == "count" {
+                // Same bounds check as `length`.
+                parse_range(&spec[..spec.find(' ').unwrap()])?;
+                Box::new(pp.policy.count_range())
+            } else {
+                // A count can be 0, a position can't.
+                let (first, second) = spec.split_once('-').unwrap();
+                if pp.policy.lower == 0 {
+                    return Err(Error::parse(Day2::DAY, first, "positions start at 1"));
+                }
+                if pp.policy.upper == 0 {
+                    let second = &second[..second.find(' ').unwrap()];
+                    return Err(Error::parse(Day2::DAY, second, "positions start at 1")
+                        .at(1, first.len() + 2));
+                }
+                Box::new(pp.policy.positions())
+            }
+        }
+        "regex" => Box::new(Pattern(
+            Regex::new(spec).map_err(|e| Error::parse(Day2::DAY, spec, e.to_string()))?,
+        )),
+        "length" => {
+            let (min, max) = parse_range(spec)?;
+            Box::new(Length { min, max })
+        }
+        "classes" => {
+            let mut column = 1;
+            let mut classes = vec![];
+            for name in spec.split(',') {
+                classes.push(
+                    name.parse::<CharClass>()
+                        .map_err(|e| Error::parse(Day2::DAY, name, e).at(1, column))?,
+                );
+                column += name.len() + 1;
+            }
+            Box::new(RequiredClasses(classes))
+        }
+        "forbidden" => {
+            // An empty substring is in every password.
+            let mut column = 1;
+            let mut substrings = vec![];
+            for substring in spec.split(',') {
+                if substring.is_empty() {
+                    return Err(Error::parse(
+                        Day2::DAY,
+                        spec,
+                        "expected a substring between commas",
+                    )
+                    .at(1, column));
+                }
+                substrings.push(substring.to_string());
+                column += substring.len() + 1;
+            }
+            Box::new(Forbidden(substrings))
+        }
+        _ => return Ok(None),
+    };
+
+    Ok(Some(policy))
+}
+
+// Lines starting with a digit are puzzle lines. Anything else is tagged with
+// its policy kind, e.g. `length 8-64: hunter2` or `classes upper,digit: hunter2`.
+// The spec runs up to the first `: `, so a regex can't contain one.
+fn parse_line(line: &str) -> Result<Entry> {
+    if line.starts_with(|c: char| c.is_ascii_digit()) {
+        return line.parse::<PolicyAndPass>().map(Entry::from);
+    }
+
+    let (tag, rest) = line
+        .split_once(' ')
+        .ok_or_else(|| Error::parse(Day2::DAY, line, "expected `<policy> <spec>: <password>`"))?;
+    let (spec, password) = rest
+        .split_once(": ")
+        .ok_or_else(|| Error::parse(Day2::DAY, line, "expected `<policy> <spec>: <password>`"))?;
+    let policy = parse_policy(tag, spec)
+        .map_err(|e| e.offset_column(tag.len() + 1))?
+        .ok_or_else(|| {
+            Error::parse(
+                Day2::DAY,
+                tag,
+                "unknown policy, expected count, positions, regex, length, classes or forbidden",
+            )
+        })?;
+
+    Ok(Entry {
+        policies: Policies::Tagged(policy),
+        password: password.to_string(),
+    })
+}
 
-    text.into_iter()
-        .map(|l| l.parse::<PolicyAndPass>().unwrap())
+fn parse_str(s: &str) -> Result<Vec<Entry>> {
+    s.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            // Only leading whitespace is dropped, trailing spaces can be part of a password.
+            parse_line(l.trim_start())
+                .map_err(|e| e.offset_line(i).offset_column(first_column(l) - 1))
+        })
         .collect()
 }
 
-fn parse() -> Vec<PolicyAndPass> {
-    let raw = fs::read_to_string("./src/day2.txt").unwrap();
-    parse_str(&raw)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Failure {
+    pub line: usize,
+    pub kind: &'static str,
+    pub policy: String,
+    // What the failure is about: the policy's token, missing classes or
+    // forbidden substrings found. Feeds the audit histograms.
+    pub subjects: Vec<String>,
+    pub verdict: Verdict,
 }
 
-pub fn part_1() {
-    let data = parse();
-    let valid = data.into_iter().filter(|pp| pp.is_password_valid()).count();
-    println!("Valid count: {}", valid)
+#[derive(Clone, Debug, PartialEq)]
+pub struct Audit {
+    pub part: u8,
+    pub checked: usize,
+    pub failures: Vec<Failure>,
 }
 
-pub fn part_2() {
-    let data = parse();
-    let valid = data
-        .into_iter()
-        .filter(|pp| pp.is_password_valid_ii())
-        .count();
-    println!("Valid count: {}", valid)
+// Entries map one to one onto input lines, so line numbers are just indices.
+pub fn audit(entries: &[Entry], part: u8) -> Audit {
+    let failures = entries
+        .iter()
+        .enumerate()
+        .filter_map(|(i, entry)| {
+            let policy = entry.policy(part);
+            let verdict = policy.evaluate(&entry.password);
+            if verdict.pass {
+                return None;
+            }
+
+            let subjects = match &verdict.observed {
+                Observed::MissingClasses(classes) => {
+                    classes.iter().map(|c| c.to_string()).collect()
+                }
+                Observed::ForbiddenFound(found) => found.clone(),
+                _ => policy.token().map(|t| t.to_string()).into_iter().collect(),
+            };
+
+            Some(Failure {
+                line: i + 1,
+                kind: policy.kind(),
+                policy: policy.to_string(),
+                subjects,
+                verdict,
+            })
+        })
+        .collect();
+
+    Audit {
+        part,
+        checked: entries.len(),
+        failures,
+    }
+}
+
+impl Audit {
+    // Failures keyed by policy kind, then cause.
+    pub fn groups(&self) -> BTreeMap<(&'static str, &'static str), Vec<&Failure>> {
+        let mut groups = BTreeMap::new();
+        for failure in &self.failures {
+            groups
+                .entry((failure.kind, failure.verdict.cause))
+                .or_insert_with(Vec::new)
+                .push(failure);
+        }
+        groups
+    }
+
+    pub fn histogram(failures: &[&Failure]) -> Counter<String> {
+        failures
+            .iter()
+            .flat_map(|f| f.subjects.iter().cloned())
+            .collect()
+    }
+
+    pub fn csv_rows(&self) -> String {
+        let mut out = String::new();
+        for f in &self.failures {
+            writeln!(
+                out,
+                "{},{},{},{},{}",
+                self.part,
+                f.line,
+                csv_field(&f.policy),
+                csv_field(f.verdict.cause),
+                csv_field(&f.verdict.reason)
+            )
+            .unwrap();
+        }
+        out
+    }
+}
+
+pub const AUDIT_CSV_HEADER: &str = "part,line,policy,cause,reason\n";
+
+// Histogram bars get scaled down to at most this many characters.
+const BAR_WIDTH: usize = 40;
+
+impl fmt::Display for Audit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "Part {}: {} of {} passwords fail",
+            self.part,
+            self.failures.len(),
+            self.checked
+        )?;
+
+        for ((kind, cause), failures) in self.groups() {
+            let lines = failures
+                .iter()
+                .map(|f| f.line.to_string())
+                .collect::<Vec<_>>();
+            writeln!(f, "\n{} / {}: {}", kind, cause, failures.len())?;
+            writeln!(f, "  lines {}", lines.join(", "))?;
+
+            let histogram = Audit::histogram(&failures).most_common_ordered();
+            let widest = histogram.iter().map(|(s, _)| s.chars().count()).max();
+            let most = histogram.first().map_or(1, |&(_, n)| n);
+            for (subject, n) in &histogram {
+                let bar = (n * BAR_WIDTH).div_ceil(most).max(1);
+                writeln!(
+                    f,
+                    "  {:<width$} {:>5} {}",
+                    subject,
+                    n,
+                    "#".repeat(bar),
+                    width = widest.unwrap_or(0)
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Solution for Day2 {
+    const DAY: u16 = 2;
+
+    type Input = Vec<Entry>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(raw: &str) -> Result<Self::Input> {
+        parse_str(raw)
+    }
+
+    fn part_1(data: &Self::Input) -> Result<Self::Part1> {
+        Ok(data.iter().filter(|entry| entry.is_valid(1)).count())
+    }
+
+    fn part_2(data: &Self::Input) -> Result<Self::Part2> {
+        Ok(data.iter().filter(|entry| entry.is_valid(2)).count())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use indoc::indoc;
 
     #[test]
     fn parse() {
@@ -118,7 +780,7 @@ mod tests {
                 policy: super::Policy {
                     lower: 5,
                     upper: 10,
-                    character: 'v'
+                    token: "v".to_string()
                 },
                 password: "vvbvsvtmtvvvvv".to_string()
             },
@@ -126,31 +788,296 @@ mod tests {
         );
     }
 
+    #[test]
+    fn parse_errors() {
+        let err = super::parse_str("1-3 a: abcde\n1-x b: cdefg").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 2 input, line 2 column 1: expected `<lower>-<upper> <token>: <password>` [1-x b: cdefg]"
+        );
+
+        // Part 2 never finds anything at position 0, but part 1 can count zero.
+        let entries = super::parse_str("0-3 a: bbb\ncount 0-3 a: bbb").unwrap();
+        assert!(entries.iter().all(|e| e.is_valid(1)));
+        assert!(!entries[0].is_valid(2));
+    }
+
     #[test]
     fn is_valid() {
         let line = "5-10 v: vvbvsvtmtvvvvv";
         let res = line.parse::<super::PolicyAndPass>().unwrap();
-        assert_eq!(res.is_password_valid(), true)
+        assert!(super::Entry::from(res).is_valid(1))
     }
 
     #[test]
     fn is_invalid() {
         let line = "1-3 b: cdefg";
         let res = line.parse::<super::PolicyAndPass>().unwrap();
-        assert_eq!(res.is_password_valid(), false)
+        assert!(!super::Entry::from(res).is_valid(1))
     }
 
     #[test]
     fn is_valid_ii() {
         let line = "1-3 a: abcde";
         let res = line.parse::<super::PolicyAndPass>().unwrap();
-        assert_eq!(res.is_password_valid_ii(), true)
+        assert!(super::Entry::from(res).is_valid(2))
     }
 
     #[test]
     fn is_invalid_ii() {
         let line = "1-3 b: cdefg";
         let res = line.parse::<super::PolicyAndPass>().unwrap();
-        assert_eq!(res.is_password_valid_ii(), false)
+        assert!(!super::Entry::from(res).is_valid(2))
+    }
+
+    #[test]
+    fn tagged_policies() {
+        let entries = super::parse_str(indoc!(
+            "
+            1-3 a: abcde
+            count 1-3 b: cdefg
+            positions 1-3 c: ccccccccc
+            regex ^[a-z]+$: lowercase
+            regex ^[a-z]+$: Mixed
+            length 4-6: four
+            length 4-6: seventy
+            classes lower,upper,digit,symbol: aB3!
+            classes digit: no digits
+            forbidden password,1234: hunter2
+            forbidden password,1234: my password
+            "
+        ))
+        .unwrap();
+        let valid = entries
+            .iter()
+            .map(|entry| entry.is_valid(2))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            valid,
+            vec![true, false, false, true, false, true, false, true, false, true, false]
+        );
+        assert_eq!(
+            entries[7].policy(1).to_string(),
+            "classes lower,upper,digit,symbol"
+        );
+        assert_eq!(entries[0].policy(1).to_string(), "count 1-3 a");
+        assert_eq!(entries[0].policy(2).to_string(), "positions 1-3 a");
+        assert_eq!(entries[2].policy(1).to_string(), "positions 1-3 c");
+    }
+
+    #[test]
+    fn tagged_parse_errors() {
+        let err = |raw: &str| super::parse_str(raw).unwrap_err().to_string();
+
+        assert_eq!(
+            err("1-3 a: abcde\nstrength 3: abc"),
+            "day 2 input, line 2 column 1: unknown policy, expected count, positions, regex, length, classes or forbidden [strength]"
+        );
+        assert_eq!(
+            err("classes lower,digits: abc"),
+            "day 2 input, line 1 column 15: expected lower, upper, digit or symbol [digits]"
+        );
+        assert_eq!(
+            err("length 4-x: abc"),
+            "day 2 input, line 1 column 10: invalid digit found in string [x]"
+        );
+        assert_eq!(
+            err("count 5-3 a: aaaa"),
+            "day 2 input, line 1 column 7: 5 is more than 3, nothing would pass [5-3]"
+        );
+        assert_eq!(
+            err("length 12-8: abc"),
+            "day 2 input, line 1 column 8: 12 is more than 8, nothing would pass [12-8]"
+        );
+        assert_eq!(
+            err("positions 0-3 a: abc"),
+            "day 2 input, line 1 column 11: positions start at 1 [0]"
+        );
+        assert_eq!(
+            err("positions 3-0 a: abc"),
+            "day 2 input, line 1 column 13: positions start at 1 [0]"
+        );
+        assert_eq!(
+            err("forbidden pass,,word: abc"),
+            "day 2 input, line 1 column 16: expected a substring between commas [pass,,word]"
+        );
+        assert_eq!(
+            err("forbidden pass,: abc"),
+            "day 2 input, line 1 column 16: expected a substring between commas [pass,]"
+        );
+        assert_eq!(
+            err("forbidden abc"),
+            "day 2 input, line 1 column 1: expected `<policy> <spec>: <password>` [forbidden abc]"
+        );
+    }
+
+    #[test]
+    fn verdicts() {
+        use super::{Observed, Verdict};
+
+        let entries = super::parse_str(indoc!(
+            "
+            1-3 a: abcde
+            1-3 b: cdefg
+            2-9 c: ccccccccc
+            length 4-6: seventy
+            classes lower,digit,symbol: abc
+            forbidden pass,word: password
+            "
+        ))
+        .unwrap();
+        let verdict = |i: usize, part: u8| entries[i].evaluate(part);
+
+        assert_eq!(
+            verdict(0, 1),
+            Verdict {
+                pass: true,
+                cause: "ok",
+                observed: Observed::Count(1),
+                reason: "count of \"a\" is 1, within 1-3".to_string()
+            }
+        );
+        assert_eq!(
+            verdict(1, 1).to_string(),
+            "fail: count of \"b\" is 0, expected 1-3"
+        );
+        assert_eq!(verdict(2, 1).observed, Observed::Count(9));
+        assert_eq!(
+            verdict(0, 2).to_string(),
+            "pass: \"a\" is at position 1 only"
+        );
+        assert_eq!(
+            verdict(1, 2).to_string(),
+            "fail: \"b\" is at neither position 1 nor 3"
+        );
+        assert_eq!(verdict(2, 2).observed, Observed::Positions(vec![2, 9]));
+        assert_eq!(
+            verdict(2, 2).to_string(),
+            "fail: \"c\" is at both positions 2 and 9"
+        );
+        assert_eq!(
+            verdict(3, 1).to_string(),
+            "fail: 7 characters long, expected 4-6"
+        );
+        assert_eq!(verdict(4, 1).to_string(), "fail: missing digit, symbol");
+        assert_eq!(verdict(5, 1).to_string(), "fail: contains pass, word");
+    }
+
+    #[test]
+    fn parts() {
+        use crate::solution::Solution;
+
+        let input = super::parse_str("1-3 a: abcde\n1-3 b: cdefg\n2-9 c: ccccccccc").unwrap();
+        assert_eq!(super::Day2::part_1(&input), Ok(2));
+        assert_eq!(super::Day2::part_2(&input), Ok(1));
+    }
+
+    #[test]
+    fn tokens_and_unicode() {
+        let entries = super::parse_str(indoc!(
+            "
+            1-2 ab: abxab yab
+            2-3 é: hé hé!
+            1-3 ☃: ☃ and ☃
+            2-3 aa: aaa
+            1-1 x:\x20
+            "
+        ))
+        .unwrap();
+
+        assert_eq!(entries[0].password, "abxab yab");
+        assert!(!entries[0].is_valid(1));
+        assert!(entries[0].is_valid(2));
+        assert!(entries[1].is_valid(1));
+        assert_eq!(
+            entries[1].evaluate(2).observed,
+            super::Observed::Positions(vec![2])
+        );
+        assert!(entries[2].is_valid(1));
+        assert_eq!(entries[3].evaluate(1).observed, super::Observed::Count(2));
+        assert_eq!(entries[4].password, "");
+    }
+
+    #[test]
+    fn bad_lines() {
+        let err = super::parse_str("1-3 a: abc\n  1-3 a abc").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 2 input, line 2 column 3: expected `<lower>-<upper> <token>: <password>` [1-3 a abc]"
+        );
+
+        // Blank lines are skipped, but errors still point at the original line.
+        assert_eq!(super::parse_str("1-3 a: abc\n\n").unwrap().len(), 1);
+        let err = super::parse_str("\n  \n1-3 a abc").unwrap_err();
+        assert!(err.to_string().starts_with("day 2 input, line 3 column 1"));
+    }
+
+    #[test]
+    fn audit() {
+        let entries = super::parse_str(indoc!(
+            "
+            1-3 a: abcde
+            1-3 b: cdefg
+            2-9 c: ccccccccc
+            1-2 b: bbbb
+            2-3 b: xyz
+            classes lower,digit,symbol: abc
+            classes digit: abc1
+            forbidden pass,word: password
+            "
+        ))
+        .unwrap();
+        let audit = super::audit(&entries, 1);
+        let groups = audit.groups();
+
+        assert_eq!(audit.checked, 8);
+        assert_eq!(
+            audit.failures.iter().map(|f| f.line).collect::<Vec<_>>(),
+            vec![2, 4, 5, 6, 8]
+        );
+        assert_eq!(
+            groups.keys().copied().collect::<Vec<_>>(),
+            vec![
+                ("classes", "missing classes"),
+                ("count", "too few"),
+                ("count", "too many"),
+                ("forbidden", "forbidden substring"),
+            ]
+        );
+        assert_eq!(
+            super::Audit::histogram(&groups[&("count", "too few")]).most_common_ordered(),
+            vec![("b".to_string(), 2)]
+        );
+        assert_eq!(
+            audit.to_string(),
+            indoc!(
+                "
+                Part 1: 5 of 8 passwords fail
+
+                classes / missing classes: 1
+                  lines 6
+                  digit      1 ########################################
+                  symbol     1 ########################################
+
+                count / too few: 2
+                  lines 2, 5
+                  b     2 ########################################
+
+                count / too many: 1
+                  lines 4
+                  b     1 ########################################
+
+                forbidden / forbidden substring: 1
+                  lines 8
+                  pass     1 ########################################
+                  word     1 ########################################
+                "
+            )
+        );
+        assert_eq!(
+            super::audit(&entries, 2).csv_rows().lines().next(),
+            Some("2,2,positions 1-3 b,at neither position,\"\"\"b\"\" is at neither position 1 nor 3\"")
+        );
     }
 }
@@ -1,99 +1,478 @@
-use std::fs;
-use std::num::ParseIntError;
+use std::collections::HashMap;
+use std::convert::{TryFrom, TryInto};
+use std::fmt;
 use std::str::FromStr;
 
-pub const DAY: u16 = 3;
+use crate::error::{first_column, Error, Result};
+use crate::grid::{self, Grid, Spot};
+use crate::solution::Solution;
 
-#[derive(Clone, Debug, PartialEq)]
-enum Place {
+pub struct Day3;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Place {
     Tree,
     Open,
 }
 
-type Matrix = Vec<Vec<Place>>;
+pub type Matrix = Grid<Place>;
 
-impl FromStr for Place {
-    type Err = ParseIntError;
-    fn from_str(letter: &str) -> Result<Self, Self::Err> {
+impl TryFrom<char> for Place {
+    type Error = String;
+    fn try_from(letter: char) -> std::result::Result<Self, Self::Error> {
         match letter {
-            "." => Ok(Place::Open),
-            "#" => Ok(Place::Tree),
-            x => panic!("[{:?}] isn't a valid character...", x),
+            '.' => Ok(Place::Open),
+            '#' => Ok(Place::Tree),
+            _ => Err("expected `.` or `#`".to_string()),
         }
     }
 }
 
-fn parse_str(s: &str) -> Matrix {
-    let text = s.lines();
+impl From<Place> for char {
+    fn from(place: Place) -> char {
+        match place {
+            Place::Open => '.',
+            Place::Tree => '#',
+        }
+    }
+}
 
-    text.into_iter()
-        .map(|line| {
-            line.chars()
-                .into_iter()
-                .filter_map(|letter| {
-                    if letter == ' ' {
-                        None
-                    } else {
-                        Some(letter.to_string().parse::<Place>().unwrap())
-                    }
-                })
-                .collect()
-        })
-        .collect()
+fn parse_str(s: &str) -> Result<Matrix> {
+    Grid::parse(Day3::DAY, s)
+}
+
+// Anything the toboggan can ride down.
+pub trait Terrain {
+    fn width(&self) -> usize;
+    fn height(&self) -> usize;
+    // Panics outside the map.
+    fn place(&self, spot: Spot) -> Place;
+
+    // Treats the map as tiled forever in every direction.
+    fn wrap(&self, row: isize, col: isize) -> Spot {
+        grid::wrap(self.width(), self.height(), row, col)
+    }
+}
+
+impl Terrain for Matrix {
+    fn width(&self) -> usize {
+        Grid::width(self)
+    }
+
+    fn height(&self) -> usize {
+        Grid::height(self)
+    }
+
+    fn place(&self, spot: Spot) -> Place {
+        self[spot]
+    }
+}
+
+// One bit per square, set for trees. Each row starts on a fresh `u64`, so a
+// map millions of squares wide takes an eighth of the bytes a `Matrix` does.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BitTerrain {
+    width: usize,
+    height: usize,
+    words_per_row: usize,
+    words: Vec<u64>,
+}
+
+impl BitTerrain {
+    // Same rules as parsing a `Matrix`, but works on bytes directly.
+    pub fn parse(raw: &str) -> Result<BitTerrain> {
+        let mut terrain = BitTerrain {
+            width: 0,
+            height: 0,
+            words_per_row: 0,
+            words: vec![],
+        };
+
+        for (y, line) in raw.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let offset = first_column(line);
+            let row = line.trim().as_bytes();
+            let bad_square = |x: usize| {
+                // Everything before was ASCII, so bytes and chars line up.
+                let c = line.trim()[x..].chars().next().unwrap_or('?');
+                Error::parse(Day3::DAY, &c.to_string(), "expected `.` or `#`").at(y + 1, x + offset)
+            };
+
+            if terrain.height == 0 {
+                terrain.width = row.len();
+                terrain.words_per_row = row.len().div_ceil(64);
+            } else if row.len() != terrain.width {
+                if let Some(x) = row.iter().position(|&b| b != b'#' && b != b'.') {
+                    return Err(bad_square(x));
+                }
+                return Err(Error::parse(
+                    Day3::DAY,
+                    line.trim(),
+                    format!("expected {} squares like the first row", terrain.width),
+                )
+                .at(y + 1, 1));
+            }
+
+            // Builds a word at a time, checking the squares as it goes.
+            let start = terrain.words.len();
+            terrain.words.resize(start + terrain.words_per_row, 0);
+            for (i, (chunk, word)) in row.chunks(64).zip(&mut terrain.words[start..]).enumerate() {
+                let (bits, bad) = pack(chunk);
+                *word = bits;
+                if bad {
+                    let x = chunk.iter().position(|&b| b != b'#' && b != b'.');
+                    return Err(bad_square(i * 64 + x.unwrap_or(0)));
+                }
+            }
+            terrain.height += 1;
+        }
+
+        if terrain.width == 0 {
+            return Err(Error::Empty { day: Day3::DAY });
+        }
+
+        Ok(terrain)
+    }
+}
+
+const LOW_BITS: u64 = 0x0101_0101_0101_0101;
+
+// Tree bits for up to 64 squares, and whether any square wasn't `.` or `#`.
+// Works 8 bytes at a time: `#` is 0x23 and `.` is 0x2e, so the low bit of
+// each byte is the tree bit, and a byte is valid when it matches the square
+// its low bit says it is.
+fn pack(chunk: &[u8]) -> (u64, bool) {
+    let (mut bits, mut bad) = (0, false);
+    let mut octets = chunk.chunks_exact(8);
+
+    for (i, octet) in octets.by_ref().enumerate() {
+        let word = u64::from_le_bytes(octet.try_into().unwrap());
+        let low = word & LOW_BITS;
+        bad |= word != low * 0x23 + (LOW_BITS ^ low) * 0x2e;
+        // Gathers the low bit of byte k into bit k of the top byte.
+        bits |= (low.wrapping_mul(0x0102_0408_1020_4080) >> 56) << (i * 8);
+    }
+
+    let done = chunk.len() - octets.remainder().len();
+    for (i, &b) in octets.remainder().iter().enumerate() {
+        bits |= u64::from(b == b'#') << (done + i);
+        bad |= b != b'#' && b != b'.';
+    }
+
+    (bits, bad)
 }
 
-fn parse() -> Matrix {
-    let raw = fs::read_to_string("./src/day3.txt").unwrap();
-    parse_str(&raw)
+impl From<&Matrix> for BitTerrain {
+    fn from(m: &Matrix) -> Self {
+        let words_per_row = m.width().div_ceil(64);
+        let mut words = vec![0; words_per_row * m.height()];
+        for (spot, &place) in m.iter() {
+            if place == Place::Tree {
+                words[spot.row * words_per_row + spot.col / 64] |= 1 << (spot.col % 64);
+            }
+        }
+
+        BitTerrain {
+            width: m.width(),
+            height: m.height(),
+            words_per_row,
+            words,
+        }
+    }
 }
 
-fn count_trees_with_slope(m: Matrix, (right, down): (usize, usize)) -> usize {
-    let mut tree_count = 0;
-    let mut x = 0;
-    let mut y = 0;
+impl Terrain for BitTerrain {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn height(&self) -> usize {
+        self.height
+    }
 
-    let m_height = m.len();
-    let m_width = m[0].len();
+    fn place(&self, spot: Spot) -> Place {
+        assert!(
+            spot.row < self.height && spot.col < self.width,
+            "{:?} is outside the map",
+            spot
+        );
 
-    while y < m_height {
-        if m[y][x] == Place::Tree {
-            tree_count += 1;
+        let word = self.words[spot.row * self.words_per_row + spot.col / 64];
+        if word >> (spot.col % 64) & 1 == 1 {
+            Place::Tree
+        } else {
+            Place::Open
         }
+    }
+}
 
-        x = (x + right) % m_width;
-        y += down;
+// Moves `right` columns (negative goes left) for every `down` rows, so
+// "right 1 every 3 down" is `Slope { right: 1, down: 3 }`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Slope {
+    pub right: isize,
+    pub down: usize,
+}
+
+impl Slope {
+    pub const fn new(right: isize, down: usize) -> Slope {
+        Slope { right, down }
     }
+}
 
-    tree_count
+// Accepts `right 3 down 1`, `right 1 every 3 down` or the fraction `1/3`.
+impl FromStr for Slope {
+    type Err = String;
+    fn from_str(raw: &str) -> std::result::Result<Self, Self::Err> {
+        let words = raw.split_whitespace().collect::<Vec<_>>();
+        let (right, down) = match words[..] {
+            ["right", right, "down", down] | ["right", right, "every", down, "down"] => {
+                (right, down)
+            }
+            [fraction] => fraction
+                .split_once('/')
+                .ok_or_else(|| format!("[{}] isn't a slope", raw))?,
+            _ => return Err(format!("[{}] isn't a slope", raw)),
+        };
+
+        let right = right
+            .parse::<isize>()
+            .map_err(|e| format!("[{}] isn't a valid right step: {}", right, e))?;
+        match down.parse::<usize>() {
+            Ok(down) if down > 0 => Ok(Slope { right, down }),
+            _ => Err(format!("[{}] isn't a valid down step", down)),
+        }
+    }
 }
 
-fn count_trees(m: Matrix) -> usize {
-    count_trees_with_slope(m, (3, 1))
+impl fmt::Display for Slope {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "right {} down {}", self.right, self.down)
+    }
 }
 
-pub fn part_1() {
-    let data = parse();
-    let tree_count = count_trees(data);
+// What happens when the toboggan reaches the side or bottom of the map.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Edges {
+    // The map repeats to the left and right, the walk ends at the bottom.
+    WrapHorizontal,
+    // The map repeats in every direction. The walk ends just before it
+    // would get back to the top left, since it only repeats from there.
+    Torus,
+    // The toboggan bounces off the left and right sides, the walk ends at
+    // the bottom.
+    Reflect,
+    // The walk ends at any edge.
+    Stop,
+}
 
-    println!("Trees encountered: {:?}", tree_count)
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Step {
+    // Where the toboggan is on the map.
+    pub spot: Spot,
+    // Column it would be in if the map's copies were laid side by side. Only
+    // differs from `spot.col` when the map wraps.
+    pub unwrapped_col: isize,
+    pub place: Place,
 }
 
-// (right, down)
-const SLOPES: [(usize, usize); 5] = [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)];
+// Bounces `col` back and forth across `0..width` without repeating the edges.
+fn reflect(col: isize, width: isize) -> isize {
+    if width == 1 {
+        return 0;
+    }
+
+    let period = 2 * (width - 1);
+    let col = col.rem_euclid(period);
+    if col < width {
+        col
+    } else {
+        period - col
+    }
+}
+
+// Every square visited from the top left until the edges end the walk.
+pub fn walk<M: Terrain>(m: &M, slope: Slope, edges: Edges) -> impl Iterator<Item = Step> + '_ {
+    assert!(slope.down > 0, "the toboggan has to go down");
+
+    let (height, width) = (m.height() as isize, m.width() as isize);
 
-pub fn part_2() {
-    let data = parse();
+    (0..).map_while(move |i: isize| {
+        let (row, col) = (i * slope.down as isize, i * slope.right);
+        let (spot, unwrapped_col) = match edges {
+            Edges::WrapHorizontal if row < height => (m.wrap(row, col), col),
+            Edges::Torus => {
+                let spot = m.wrap(row, col);
+                if i > 0 && spot == (Spot { row: 0, col: 0 }) {
+                    return None;
+                }
+                (spot, col)
+            }
+            Edges::Reflect if row < height => {
+                let col = reflect(col, width);
+                (m.wrap(row, col), col)
+            }
+            Edges::Stop if row < height && (0..width).contains(&col) => (m.wrap(row, col), col),
+            _ => return None,
+        };
 
-    let result: usize = SLOPES
+        Some(Step {
+            spot,
+            unwrapped_col,
+            place: m.place(spot),
+        })
+    })
+}
+
+pub fn trace<M: Terrain>(m: &M, slope: Slope, edges: Edges) -> Vec<Step> {
+    walk(m, slope, edges).collect()
+}
+
+// The map tiled wide enough to fit the path, with open squares visited
+// marked `O` and trees hit marked `X`.
+pub fn render_path<M: Terrain>(m: &M, path: &[Step]) -> String {
+    let width = m.width() as isize;
+    let tile = |col: isize| col.div_euclid(width);
+    let first = path
+        .iter()
+        .map(|s| tile(s.unwrapped_col))
+        .min()
+        .unwrap_or(0);
+    let last = path
         .iter()
-        .map(|s| count_trees_with_slope(data.clone(), *s))
-        .product();
+        .map(|s| tile(s.unwrapped_col))
+        .max()
+        .unwrap_or(0);
+    let visited = path
+        .iter()
+        .map(|s| ((s.spot.row, s.unwrapped_col), s.place))
+        .collect::<HashMap<_, _>>();
+
+    let rows = (0..m.height())
+        .map(|row| {
+            (first * width..(last + 1) * width)
+                .map(|col| match visited.get(&(row, col)) {
+                    Some(Place::Tree) => 'X',
+                    Some(Place::Open) => 'O',
+                    None => m.place(m.wrap(row as isize, col)).into(),
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<_>>();
+
+    rows.join("\n")
+}
+
+fn count_trees_with_slope<M: Terrain>(m: &M, slope: Slope) -> usize {
+    walk(m, slope, Edges::WrapHorizontal)
+        .filter(|step| step.place == Place::Tree)
+        .count()
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Goal {
+    FewestTrees,
+    MostTrees,
+}
+
+// Inclusive bounds on the slopes to try.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SlopeBounds {
+    pub min_right: isize,
+    pub max_right: isize,
+    pub max_down: usize,
+}
 
-    println!("The product of tree counts at slopes: {}", result);
+#[derive(Clone, Debug, PartialEq)]
+pub struct BestSlopes {
+    pub trees: usize,
+    // Every slope that ties, in the order they were tried.
+    pub slopes: Vec<Slope>,
+}
+
+// Tries every slope within `bounds`, down first then right. When looking for
+// the fewest trees a walk is abandoned as soon as it can't tie the best so far.
+pub fn best_slopes<M: Terrain>(m: &M, bounds: SlopeBounds, goal: Goal) -> Option<BestSlopes> {
+    let mut best: Option<BestSlopes> = None;
+
+    for down in 1..=bounds.max_down {
+        for right in bounds.min_right..=bounds.max_right {
+            let slope = Slope::new(right, down);
+            let limit = match (&best, goal) {
+                (Some(b), Goal::FewestTrees) => b.trees,
+                _ => usize::MAX,
+            };
+
+            let mut trees = 0;
+            for step in walk(m, slope, Edges::WrapHorizontal) {
+                if step.place == Place::Tree {
+                    trees += 1;
+                    if trees > limit {
+                        break;
+                    }
+                }
+            }
+
+            let better = match (&best, goal) {
+                (None, _) => true,
+                (Some(b), Goal::FewestTrees) => trees < b.trees,
+                (Some(b), Goal::MostTrees) => trees > b.trees,
+            };
+            if better {
+                best = Some(BestSlopes {
+                    trees,
+                    slopes: vec![slope],
+                });
+            } else if let Some(b) = best.as_mut().filter(|b| b.trees == trees) {
+                b.slopes.push(slope);
+            }
+        }
+    }
+
+    best
+}
+
+fn count_trees(m: &Matrix) -> usize {
+    count_trees_with_slope(m, Slope::new(3, 1))
+}
+
+// The slopes part 2 multiplies together.
+pub const SLOPES: [Slope; 5] = [
+    Slope::new(1, 1),
+    Slope::new(3, 1),
+    Slope::new(5, 1),
+    Slope::new(7, 1),
+    Slope::new(1, 2),
+];
+
+impl Solution for Day3 {
+    const DAY: u16 = 3;
+
+    type Input = Matrix;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(raw: &str) -> Result<Self::Input> {
+        parse_str(raw)
+    }
+
+    fn part_1(data: &Self::Input) -> Result<Self::Part1> {
+        Ok(count_trees(data))
+    }
+
+    fn part_2(data: &Self::Input) -> Result<Self::Part2> {
+        Ok(SLOPES
+            .iter()
+            .map(|&s| count_trees_with_slope(data, s))
+            .product())
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use indoc::indoc;
 
     const RAW_DATA: &str = "..##.......
     #...#...#..
@@ -109,27 +488,287 @@ mod tests {
 
     #[test]
     fn parse() {
-        let data = super::parse_str(RAW_DATA);
+        let data = super::parse_str(RAW_DATA).unwrap();
+
+        assert_eq!(super::Place::Tree, data[super::Spot { row: 0, col: 2 }]);
+    }
 
-        assert_eq!(super::Place::Tree, data[0][2]);
+    #[test]
+    fn parse_errors() {
+        assert_eq!(
+            super::parse_str("..#\n.X.").unwrap_err().to_string(),
+            "day 3 input, line 2 column 2: expected `.` or `#` [X]"
+        );
+        assert_eq!(
+            super::parse_str("..#\n.#").unwrap_err().to_string(),
+            "day 3 input, line 2 column 1: expected 3 squares like the first row [.#]"
+        );
+        assert_eq!(
+            super::parse_str(""),
+            Err(crate::error::Error::Empty { day: 3 })
+        );
     }
 
     #[test]
     fn count_trees() {
-        let data = super::parse_str(RAW_DATA);
+        let data = super::parse_str(RAW_DATA).unwrap();
 
-        assert_eq!(super::count_trees(data), 7);
+        assert_eq!(super::count_trees(&data), 7);
     }
 
     #[test]
     fn count_trees_with_slope() {
-        let data = super::parse_str(RAW_DATA);
+        let data = super::parse_str(RAW_DATA).unwrap();
 
         let result = super::SLOPES
             .iter()
-            .map(|s| super::count_trees_with_slope(data.clone(), *s))
+            .map(|&s| super::count_trees_with_slope(&data, s))
             .collect::<Vec<_>>();
 
         assert_eq!([2, 7, 3, 4, 2], result[..]);
     }
+
+    #[test]
+    fn slopes() {
+        use super::Slope;
+
+        assert_eq!("right 3 down 1".parse(), Ok(Slope::new(3, 1)));
+        assert_eq!("right 1 every 3 down".parse(), Ok(Slope::new(1, 3)));
+        assert_eq!("-2/1".parse(), Ok(Slope::new(-2, 1)));
+        assert_eq!(Slope::new(-2, 1).to_string(), "right -2 down 1");
+        assert!("right 1 down 0".parse::<Slope>().is_err());
+        assert!("up 1".parse::<Slope>().is_err());
+    }
+
+    #[test]
+    fn trace() {
+        use super::{Edges, Place, Slope, Spot};
+
+        let data = super::parse_str(RAW_DATA).unwrap();
+        let path = super::trace(&data, Slope::new(3, 1), Edges::WrapHorizontal);
+
+        assert_eq!(path.len(), 11);
+        assert_eq!(path[4].spot, Spot { row: 4, col: 1 });
+        assert_eq!(path[4].unwrapped_col, 12);
+        assert_eq!(path[4].place, Place::Tree);
+
+        let left = super::trace(&data, Slope::new(-1, 3), Edges::WrapHorizontal);
+        assert_eq!(
+            left.iter().map(|s| s.spot).collect::<Vec<_>>(),
+            vec![
+                Spot { row: 0, col: 0 },
+                Spot { row: 3, col: 10 },
+                Spot { row: 6, col: 9 },
+                Spot { row: 9, col: 8 },
+            ]
+        );
+    }
+
+    #[test]
+    fn render_path() {
+        let data = super::parse_str(RAW_DATA).unwrap();
+        let path = super::trace(&data, super::Slope::new(3, 1), super::Edges::WrapHorizontal);
+
+        assert_eq!(
+            super::render_path(&data, &path),
+            indoc!(
+                "O.##.........##.........##.......
+                #..O#...#..#...#...#..#...#...#..
+                .#....X..#..#....#..#..#....#..#.
+                ..#.#...#O#..#.#...#.#..#.#...#.#
+                .#...##..#..X...##..#..#...##..#.
+                ..#.##.......#.X#.......#.##.....
+                .#.#.#....#.#.#.#.O..#.#.#.#....#
+                .#........#.#........X.#........#
+                #.##...#...#.##...#...#.X#...#...
+                #...##....##...##....##...#X....#
+                .#..#...#.#.#..#...#.#.#..#...X.#"
+            )
+        );
+    }
+
+    #[test]
+    fn best_slopes() {
+        use super::{BestSlopes, Goal, Slope, SlopeBounds};
+
+        let data = super::parse_str(RAW_DATA).unwrap();
+        let bounds = SlopeBounds {
+            min_right: -2,
+            max_right: 3,
+            max_down: 2,
+        };
+
+        assert_eq!(
+            super::best_slopes(&data, bounds, Goal::FewestTrees),
+            Some(BestSlopes {
+                trees: 1,
+                slopes: vec![Slope::new(2, 1), Slope::new(0, 2), Slope::new(2, 2)],
+            })
+        );
+        assert_eq!(
+            super::best_slopes(&data, bounds, Goal::MostTrees),
+            Some(BestSlopes {
+                trees: 7,
+                slopes: vec![Slope::new(3, 1)],
+            })
+        );
+        assert_eq!(
+            super::best_slopes(
+                &data,
+                SlopeBounds {
+                    max_down: 0,
+                    ..bounds
+                },
+                Goal::MostTrees
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn edges() {
+        use super::{Edges, Slope, Spot};
+
+        let data = super::parse_str(indoc!(
+            "
+            ..#
+            #..
+            .#.
+            ..#
+            "
+        ))
+        .unwrap();
+        let spots = |slope: Slope, edges: Edges| {
+            super::walk(&data, slope, edges)
+                .map(|s| (s.spot.row, s.spot.col))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            spots(Slope::new(2, 1), Edges::WrapHorizontal),
+            vec![(0, 0), (1, 2), (2, 1), (3, 0)]
+        );
+        // Back to the top left after lcm(4, 3) = 12 steps.
+        assert_eq!(spots(Slope::new(1, 1), Edges::Torus).len(), 12);
+        assert_eq!(
+            spots(Slope::new(1, 3), Edges::Torus),
+            vec![
+                (0, 0),
+                (3, 1),
+                (2, 2),
+                (1, 0),
+                (0, 1),
+                (3, 2),
+                (2, 0),
+                (1, 1),
+                (0, 2),
+                (3, 0),
+                (2, 1),
+                (1, 2)
+            ]
+        );
+        assert_eq!(
+            spots(Slope::new(2, 1), Edges::Reflect),
+            vec![(0, 0), (1, 2), (2, 0), (3, 2)]
+        );
+        assert_eq!(
+            spots(Slope::new(-1, 1), Edges::Reflect),
+            vec![(0, 0), (1, 1), (2, 2), (3, 1)]
+        );
+        assert_eq!(spots(Slope::new(2, 1), Edges::Stop), vec![(0, 0), (1, 2)]);
+        assert_eq!(spots(Slope::new(-1, 1), Edges::Stop), vec![(0, 0)]);
+        assert_eq!(spots(Slope::new(0, 2), Edges::Stop), vec![(0, 0), (2, 0)]);
+
+        let bounced = super::trace(&data, Slope::new(2, 1), Edges::Reflect);
+        assert_eq!(bounced[1].spot, Spot { row: 1, col: 2 });
+        assert_eq!(bounced[1].unwrapped_col, 2);
+        assert_eq!(
+            bounced
+                .iter()
+                .filter(|s| s.place == super::Place::Tree)
+                .count(),
+            1
+        );
+    }
+
+    #[test]
+    fn bit_terrain() {
+        use super::{BitTerrain, Slope, Spot, Terrain};
+
+        let data = super::parse_str(RAW_DATA).unwrap();
+        let bits = BitTerrain::parse(RAW_DATA).unwrap();
+
+        assert_eq!(BitTerrain::from(&data), bits);
+        assert_eq!((bits.width(), bits.height()), (11, 11));
+        assert_eq!(
+            BitTerrain::parse(&format!("\n{}\n\n", RAW_DATA)).unwrap(),
+            bits
+        );
+        assert!(data.iter().all(|(spot, &place)| bits.place(spot) == place));
+        assert_eq!(
+            super::SLOPES
+                .iter()
+                .map(|&s| super::count_trees_with_slope(&bits, s))
+                .collect::<Vec<_>>(),
+            [2, 7, 3, 4, 2]
+        );
+
+        // Rows spanning several words.
+        let wide = format!("{}#\n{}", ".".repeat(129), "#".repeat(130));
+        let bits = BitTerrain::parse(&wide).unwrap();
+        assert_eq!(bits.place(Spot { row: 0, col: 128 }), super::Place::Open);
+        assert_eq!(bits.place(Spot { row: 0, col: 129 }), super::Place::Tree);
+        assert_eq!(
+            super::trace(&bits, Slope::new(129, 1), super::Edges::WrapHorizontal)
+                .iter()
+                .map(|s| s.place)
+                .collect::<Vec<_>>(),
+            super::trace(
+                &super::parse_str(&wide).unwrap(),
+                Slope::new(129, 1),
+                super::Edges::WrapHorizontal
+            )
+            .iter()
+            .map(|s| s.place)
+            .collect::<Vec<_>>()
+        );
+
+        let mixed = (0..3)
+            .map(|row| {
+                (0..150)
+                    .map(|col| {
+                        if (row * 7 + col * 13) % 5 < 2 {
+                            '#'
+                        } else {
+                            '.'
+                        }
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let bits = BitTerrain::parse(&mixed).unwrap();
+        let data = super::parse_str(&mixed).unwrap();
+        assert!(data.iter().all(|(spot, &place)| bits.place(spot) == place));
+
+        let mut bad = ".".repeat(100);
+        bad.replace_range(40..41, "!");
+        assert_eq!(
+            BitTerrain::parse(&bad).unwrap_err().to_string(),
+            "day 3 input, line 1 column 41: expected `.` or `#` [!]"
+        );
+        assert_eq!(
+            BitTerrain::parse("..#\n.X.").unwrap_err().to_string(),
+            "day 3 input, line 2 column 2: expected `.` or `#` [X]"
+        );
+        assert_eq!(
+            BitTerrain::parse("..#\n.é.").unwrap_err().to_string(),
+            "day 3 input, line 2 column 2: expected `.` or `#` [é]"
+        );
+        assert_eq!(
+            BitTerrain::parse("..#\n.#").unwrap_err().to_string(),
+            "day 3 input, line 2 column 1: expected 3 squares like the first row [.#]"
+        );
+        assert!(BitTerrain::parse("").is_err());
+    }
 }
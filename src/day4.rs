@@ -1,227 +1,666 @@
 use regex::Regex;
 use std::collections::{BTreeMap, BTreeSet};
-use std::convert::Infallible;
-use std::fs;
+use std::convert::TryFrom;
+use std::fmt;
 use std::str::FromStr;
 
-pub const DAY: u16 = 4;
+use toml::Value;
 
+use crate::error::{self, Error};
+use crate::solution::{self, Run, Runnable, Solution};
+
+pub struct Day4;
+
+// A record as it appears in the input, before any of its values are checked.
 #[derive(Clone, Debug, PartialEq)]
-enum Identity {
-    Passport(BTreeMap<String, String>),
-    Invalid(BTreeMap<String, String>),
-}
-
-type ValidatorFn = Box<fn(String) -> Result<(), String>>;
-
-struct Validator {
-    key: String,
-    f: ValidatorFn,
-}
-
-impl Identity {
-    fn is_valid(&self) -> bool {
-        let validators: Vec<Validator> = vec![
-            Validator {
-                key: "byr".to_string(),
-                // byr (Birth Year) - four digits; at least 1920 and at most 2002.
-                f: Box::new(|s| match s.parse::<u32>() {
-                    Ok(num) => match num {
-                        1920..=2002 => Ok(()),
-                        _ => Err("Outside range".to_string()),
-                    },
-                    Err(e) => Err(e.to_string()),
-                }),
-            },
-            Validator {
-                key: "iyr".to_string(),
-
-                // iyr (Issue Year) - four digits; at least 2010 and at most 2020.
-                f: Box::new(|s| match s.parse::<u32>() {
-                    Ok(num) => match num {
-                        2010..=2020 => Ok(()),
-                        _ => Err("Outside range".to_string()),
-                    },
-                    Err(e) => Err(e.to_string()),
-                }),
-            },
-            Validator {
-                key: "eyr".to_string(),
-                // eyr (Expiration Year) - four digits; at least 2020 and at most 2030.
-                f: Box::new(|s| match s.parse::<u32>() {
-                    Ok(num) => match num {
-                        2020..=2030 => Ok(()),
-                        _ => Err("Outside range".to_string()),
-                    },
-                    Err(e) => Err(e.to_string()),
-                }),
-            },
-            Validator {
-                key: "hgt".to_string(),
-                // hgt (Height) - a number followed by either cm or in:
-                // If cm, the number must be at least 150 and at most 193.
-                // If in, the number must be at least 59 and at most 76.
-                f: Box::new(|s| {
-                    lazy_static! {
-                        static ref RE: Regex = Regex::new(r"^(\d+)(cm|in)$",).unwrap();
-                    }
+pub struct RawRecord {
+    // 1-based line the record starts on.
+    pub line: usize,
+    pub fields: BTreeMap<String, String>,
+}
 
-                    let captures = RE.captures(&s);
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Height {
+    Cm(u32),
+    In(u32),
+}
 
-                    if captures.is_none() {
-                        return Err(format!("{} doesn't match regex", s));
-                    }
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
 
-                    let matches = captures.unwrap();
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum EyeColor {
+    Amber,
+    Blue,
+    Brown,
+    Gray,
+    Green,
+    Hazel,
+    Other,
+}
 
-                    match matches[1].parse::<u32>() {
-                        Ok(num) => match (num, &matches[2]) {
-                            (150..=193, "cm") => Ok(()),
-                            (59..=76, "in") => Ok(()),
-                            _ => Err("Outside range".to_string()),
-                        },
-                        Err(e) => Err(e.to_string()),
-                    }
-                }),
-            },
-            Validator {
-                key: "hcl".to_string(),
-                // hcl (Hair Color) - a # followed by exactly six characters 0-9 or a-f.
-                f: Box::new(|s| {
-                    lazy_static! {
-                        static ref RE: Regex = Regex::new(r"^#([0-9a-f]{6})$").unwrap();
-                    }
+// A record whose fields passed the schema and fit their types.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Passport {
+    pub birth_year: u16,
+    pub issue_year: u16,
+    pub expiration_year: u16,
+    pub height: Height,
+    pub hair_color: Rgb,
+    pub eye_color: EyeColor,
+    // One digit per element, leading zeroes included.
+    pub passport_id: [u8; 9],
+    pub country_id: Option<String>,
+}
 
-                    if RE.is_match(&s) {
-                        return Ok(());
-                    }
+pub const DEFAULT_SCHEMA: &str = include_str!("day4_schema.toml");
 
-                    Err(format!("{} doesn't match regex", s))
-                }),
-            },
-            Validator {
-                key: "ecl".to_string(),
-                // ecl (Eye Color) - exactly one of: amb blu brn gry grn hzl oth.
-                f: Box::new(|s| match s.as_str() {
-                    "amb" | "blu" | "brn" | "gry" | "grn" | "hzl" | "oth" => Ok(()),
-                    _ => Err(s),
-                }),
-            },
-            // TODO regex
-            Validator {
-                key: "pid".to_string(),
-                // pid (Passport ID) - a nine-digit number, including leading zeroes.
-                f: Box::new(|s| {
-                    lazy_static! {
-                        static ref RE: Regex = Regex::new(r"^(\d{9})$").unwrap();
-                    }
+#[derive(Clone, Debug)]
+pub enum FieldType {
+    Any,
+    IntRange { min: i64, max: i64 },
+    // A number directly followed by a unit, e.g. `183cm`. Each unit has its own range.
+    Units(BTreeMap<String, (i64, i64)>),
+    Pattern(Regex),
+    OneOf(BTreeSet<String>),
+}
 
-                    if RE.is_match(&s) {
-                        return Ok(());
-                    }
+#[derive(Clone, Debug)]
+pub struct FieldRule {
+    pub required: bool,
+    pub kind: FieldType,
+}
 
-                    Err(format!("{} doesn't match regex", s))
-                }),
-            },
-        ];
-        if let Identity::Passport(m) = &self {
-            return validators.into_iter().all(|Validator { key, f }| {
-                let raw = &m[&key];
-
-                let result = f(raw.to_string());
-                if result.is_err() {
-                    return false;
+fn check_range(n: i64, (min, max): (i64, i64)) -> Result<(), String> {
+    if (min..=max).contains(&n) {
+        Ok(())
+    } else {
+        Err(format!("{} is outside {}-{}", n, min, max))
+    }
+}
+
+impl FieldRule {
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        match &self.kind {
+            FieldType::Any => Ok(()),
+            FieldType::IntRange { min, max } => {
+                let n = value.parse::<i64>().map_err(|e| e.to_string())?;
+                check_range(n, (*min, *max))
+            }
+            FieldType::Units(units) => {
+                let split = value
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(value.len());
+                let (number, unit) = value.split_at(split);
+                let &range = units.get(unit).ok_or_else(|| {
+                    let known = units.keys().cloned().collect::<Vec<_>>();
+                    format!("{} doesn't end in one of {}", value, known.join(", "))
+                })?;
+                let n = number.parse::<i64>().map_err(|e| e.to_string())?;
+                check_range(n, range)
+            }
+            FieldType::Pattern(re) => {
+                if re.is_match(value) {
+                    Ok(())
+                } else {
+                    Err(format!("{} doesn't match {}", value, re))
+                }
+            }
+            FieldType::OneOf(values) => {
+                if values.contains(value) {
+                    Ok(())
+                } else {
+                    let values = values.iter().cloned().collect::<Vec<_>>();
+                    Err(format!("{} isn't one of {}", value, values.join(", ")))
                 }
+            }
+        }
+    }
+}
 
-                true
-            });
+// Which fields a passport has and what their values look like. See
+// `day4_schema.toml` for the format, which is also the default schema.
+#[derive(Clone, Debug)]
+pub struct Schema {
+    pub fields: BTreeMap<String, FieldRule>,
+}
+
+impl Schema {
+    pub fn required(&self) -> impl Iterator<Item = &str> {
+        self.fields
+            .iter()
+            .filter(|(_, rule)| rule.required)
+            .map(|(key, _)| key.as_str())
+    }
+
+    // Every required field is present and every known field is valid.
+    pub fn is_valid(&self, passport: &BTreeMap<String, String>) -> bool {
+        self.fields
+            .iter()
+            .all(|(key, rule)| match passport.get(key) {
+                Some(value) => rule.check(value).is_ok(),
+                None => !rule.required,
+            })
+    }
+
+    pub fn diagnose(&self, line: usize, passport: &BTreeMap<String, String>) -> Diagnosis {
+        let mut diagnosis = Diagnosis {
+            line,
+            ..Diagnosis::default()
+        };
+
+        for (key, rule) in &self.fields {
+            match passport.get(key) {
+                Some(value) => {
+                    if let Err(reason) = rule.check(value) {
+                        diagnosis.invalid.push(InvalidField {
+                            key: key.clone(),
+                            value: value.clone(),
+                            reason,
+                        });
+                    }
+                }
+                None if rule.required => diagnosis.missing.push(key.clone()),
+                None => {}
+            }
         }
+        diagnosis.unknown = passport
+            .keys()
+            .filter(|key| !self.fields.contains_key(*key))
+            .cloned()
+            .collect();
 
-        false
+        diagnosis
+    }
+
+    // The first stage of turning a record into a `Passport`.
+    pub fn validate<'a>(&self, record: &'a RawRecord) -> Result<ValidRecord<'a>, Diagnosis> {
+        if self.is_valid(&record.fields) {
+            Ok(ValidRecord(record))
+        } else {
+            Err(record.diagnose(self))
+        }
     }
 }
 
-impl FromStr for Identity {
-    type Err = Infallible;
-    fn from_str(lines: &str) -> Result<Self, Self::Err> {
-        let tokens = lines.split_ascii_whitespace();
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidField {
+    pub key: String,
+    pub value: String,
+    pub reason: String,
+}
 
-        let required_fields = btreeset! {
-            "byr",
-            "ecl",
-            "eyr",
-            "hcl",
-            "hgt",
-            "iyr",
-            "pid",
-        };
+// Everything wrong with one record, found on `line` of the input.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Diagnosis {
+    pub line: usize,
+    pub missing: Vec<String>,
+    pub invalid: Vec<InvalidField>,
+    // Fields the schema doesn't know. They don't make a passport invalid.
+    pub unknown: Vec<String>,
+    // Why a record the schema accepted still isn't a `Passport`.
+    pub untyped: Option<String>,
+}
+
+impl Diagnosis {
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.invalid.is_empty()
+    }
+}
+
+impl fmt::Display for Diagnosis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let verdict = if self.is_valid() { "valid" } else { "invalid" };
+        writeln!(f, "line {}: {}", self.line, verdict)?;
+        if !self.missing.is_empty() {
+            writeln!(f, "  missing {}", self.missing.join(", "))?;
+        }
+        for field in &self.invalid {
+            writeln!(f, "  {}: {}", field.key, field.reason)?;
+        }
+        if !self.unknown.is_empty() {
+            writeln!(f, "  ignored unknown {}", self.unknown.join(", "))?;
+        }
+        if let Some(reason) = &self.untyped {
+            writeln!(f, "  not a passport: {}", reason)?;
+        }
+        Ok(())
+    }
+}
+
+// Diagnoses for every record in the input, in order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub diagnoses: Vec<Diagnosis>,
+}
+
+pub fn report(raw: &str, schema: &Schema) -> error::Result<Report> {
+    let records = parse_str(raw)?;
+    let diagnoses = records
+        .iter()
+        .zip(passports(&records, schema))
+        .map(|(record, passport)| match passport {
+            Ok(_) => record.diagnose(schema),
+            Err(PassportError::Rejected(diagnosis)) => diagnosis,
+            Err(e) => Diagnosis {
+                untyped: Some(e.to_string()),
+                ..record.diagnose(schema)
+            },
+        })
+        .collect();
 
-        let mut kv = btreemap! {};
+    Ok(Report { diagnoses })
+}
+
+impl Report {
+    pub fn valid(&self) -> usize {
+        self.diagnoses.iter().filter(|d| d.is_valid()).count()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} of {} passports are valid",
+            self.valid(),
+            self.diagnoses.len()
+        )?;
+        for diagnosis in &self.diagnoses {
+            write!(f, "\n{}", diagnosis)?;
+        }
+        Ok(())
+    }
+}
 
-        for (key, val) in tokens.filter_map(|t| {
-            let split = t.split(':').collect::<Vec<_>>();
-            match split[..] {
-                [key, val] => Some((key, val)),
-                _ => None,
+fn parse_rule(key: &str, spec: &Value) -> Result<FieldRule, String> {
+    let spec = spec
+        .as_table()
+        .ok_or_else(|| format!("[fields.{}] should be a table", key))?;
+    let int = |table: &toml::value::Table, name: &str, at: &str| {
+        table
+            .get(name)
+            .and_then(Value::as_integer)
+            .ok_or_else(|| format!("[{}] is missing integer {}", at, name))
+    };
+    let at = format!("fields.{}", key);
+
+    let required = match spec.get("required") {
+        None => true,
+        Some(Value::Boolean(b)) => *b,
+        Some(x) => return Err(format!("[{}] has non-boolean required {}", at, x)),
+    };
+    let kind = match spec.get("type").map(|t| t.as_str()) {
+        None | Some(Some("any")) => FieldType::Any,
+        Some(Some("int")) => FieldType::IntRange {
+            min: int(spec, "min", &at)?,
+            max: int(spec, "max", &at)?,
+        },
+        Some(Some("unit")) => {
+            let units = spec
+                .get("units")
+                .and_then(Value::as_table)
+                .filter(|units| !units.is_empty())
+                .ok_or_else(|| format!("[{}] is missing a table of units", at))?;
+            let mut ranges = BTreeMap::new();
+            for (unit, range) in units {
+                let range = range
+                    .as_table()
+                    .ok_or_else(|| format!("[{}.units.{}] should be a table", at, unit))?;
+                let at = format!("{}.units.{}", at, unit);
+                ranges.insert(
+                    unit.clone(),
+                    (int(range, "min", &at)?, int(range, "max", &at)?),
+                );
             }
-        }) {
-            kv.insert(key, val);
+            FieldType::Units(ranges)
+        }
+        Some(Some("regex")) => {
+            let pattern = spec
+                .get("pattern")
+                .and_then(Value::as_str)
+                .ok_or_else(|| format!("[{}] is missing pattern", at))?;
+            FieldType::Pattern(
+                Regex::new(pattern).map_err(|e| format!("[{}] has a bad pattern: {}", at, e))?,
+            )
+        }
+        Some(Some("enum")) => FieldType::OneOf(
+            spec.get("values")
+                .and_then(Value::as_array)
+                .and_then(|values| {
+                    values
+                        .iter()
+                        .map(|v| v.as_str().map(String::from))
+                        .collect()
+                })
+                .ok_or_else(|| format!("[{}] is missing a list of string values", at))?,
+        ),
+        _ => {
+            return Err(format!(
+                "[{}] has an unknown type, expected int, unit, regex, enum or any",
+                at
+            ))
+        }
+    };
+
+    Ok(FieldRule { required, kind })
+}
+
+impl FromStr for Schema {
+    type Err = String;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let table = raw.parse::<Value>().map_err(|e| e.to_string())?;
+        let fields = table
+            .get("fields")
+            .and_then(Value::as_table)
+            .ok_or("expected a [fields] table")?;
+
+        Ok(Schema {
+            fields: fields
+                .iter()
+                .map(|(key, spec)| Ok((key.clone(), parse_rule(key, spec)?)))
+                .collect::<Result<_, String>>()?,
+        })
+    }
+}
+
+lazy_static! {
+    static ref DEFAULT: Schema = DEFAULT_SCHEMA.parse().expect("the default schema is valid");
+}
+
+// What `Solution::part_*` check passports against.
+pub fn default_schema() -> &'static Schema {
+    &DEFAULT
+}
+
+// Whitespace separated tokens, along with their 1-based line and column.
+fn tokens(lines: &str) -> impl Iterator<Item = (usize, usize, &str)> {
+    lines.lines().enumerate().flat_map(|(y, line)| {
+        line.split(|c: char| c.is_ascii_whitespace())
+            .scan(0, |column, token| {
+                let start = *column;
+                *column += token.len() + 1;
+                Some((start, token))
+            })
+            .filter(|(_, token)| !token.is_empty())
+            .map(move |(x, token)| (y + 1, x + 1, token))
+    })
+}
+
+impl FromStr for RawRecord {
+    type Err = Error;
+    fn from_str(lines: &str) -> error::Result<Self> {
+        let mut fields = BTreeMap::new();
+
+        for (y, x, token) in tokens(lines) {
+            match token.split(':').collect::<Vec<_>>()[..] {
+                [key, val] => fields.insert(key.to_string(), val.to_string()),
+                _ => return Err(Error::parse(Day4::DAY, token, "expected `key:value`").at(y, x)),
+            };
         }
 
-        let current_keys = kv.keys().copied().collect::<BTreeSet<_>>();
-        let result = kv
-            .into_iter()
-            .map(|(k, v)| (k.to_string(), v.to_string()))
-            .collect::<BTreeMap<_, _>>();
+        Ok(RawRecord { line: 1, fields })
+    }
+}
 
-        if required_fields.is_subset(&current_keys) {
-            return Ok(Identity::Passport(result));
+// Records are separated by blank lines. Runs of extra blank lines don't make
+// empty records.
+fn parse_str(s: &str) -> error::Result<Vec<RawRecord>> {
+    let mut line = 0;
+    let mut records = vec![];
+
+    for record in s.split("\n\n") {
+        let trimmed = record.trim_start_matches('\n');
+        line += record.len() - trimmed.len();
+        if !trimmed.trim().is_empty() {
+            records.push(RawRecord {
+                line: line + 1,
+                ..trimmed
+                    .parse::<RawRecord>()
+                    .map_err(|e| e.offset_line(line))?
+            });
         }
+        line += trimmed.matches('\n').count() + 2;
+    }
 
-        Ok(Identity::Invalid(result))
+    Ok(records)
+}
+
+impl RawRecord {
+    pub fn has_required_fields(&self, schema: &Schema) -> bool {
+        schema.required().all(|key| self.fields.contains_key(key))
+    }
+
+    pub fn diagnose(&self, schema: &Schema) -> Diagnosis {
+        schema.diagnose(self.line, &self.fields)
     }
 }
 
-fn parse_str(s: &str) -> Vec<Identity> {
-    // Segment
-    let text = s.split("\n\n");
+impl FromStr for Height {
+    type Err = String;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let split = raw.find(|c: char| !c.is_ascii_digit()).unwrap_or(raw.len());
+        let n = raw[..split].parse::<u32>().map_err(|e| e.to_string());
 
-    text.into_iter()
-        .map(|l| l.parse::<Identity>().unwrap())
-        .collect()
+        match &raw[split..] {
+            "cm" => Ok(Height::Cm(n?)),
+            "in" => Ok(Height::In(n?)),
+            _ => Err(format!("{} isn't in cm or in", raw)),
+        }
+    }
+}
+
+impl fmt::Display for Height {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Height::Cm(n) => write!(f, "{}cm", n),
+            Height::In(n) => write!(f, "{}in", n),
+        }
+    }
 }
 
-fn parse() -> Vec<Identity> {
-    let raw = fs::read_to_string("./src/day4.txt").unwrap();
-    parse_str(&raw)
+impl FromStr for Rgb {
+    type Err = String;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        let hex = raw
+            .strip_prefix('#')
+            .filter(|hex| hex.len() == 6 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| format!("{} isn't a #rrggbb color", raw))?;
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap();
+
+        Ok(Rgb {
+            red: channel(0),
+            green: channel(2),
+            blue: channel(4),
+        })
+    }
 }
 
-pub fn part_1() {
-    let data = parse();
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.red, self.green, self.blue)
+    }
+}
 
-    let valid_passports = data
-        .into_iter()
-        .filter_map(|d| match d {
-            Identity::Passport(_) => Some(()),
-            Identity::Invalid(_) => None,
+impl FromStr for EyeColor {
+    type Err = String;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        Ok(match raw {
+            "amb" => EyeColor::Amber,
+            "blu" => EyeColor::Blue,
+            "brn" => EyeColor::Brown,
+            "gry" => EyeColor::Gray,
+            "grn" => EyeColor::Green,
+            "hzl" => EyeColor::Hazel,
+            "oth" => EyeColor::Other,
+            _ => return Err(format!("{} isn't an eye color", raw)),
         })
-        .count();
+    }
+}
 
-    println!("Valid passport count {}", valid_passports);
+impl fmt::Display for EyeColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let code = match self {
+            EyeColor::Amber => "amb",
+            EyeColor::Blue => "blu",
+            EyeColor::Brown => "brn",
+            EyeColor::Gray => "gry",
+            EyeColor::Green => "grn",
+            EyeColor::Hazel => "hzl",
+            EyeColor::Other => "oth",
+        };
+        write!(f, "{}", code)
+    }
+}
+
+fn parse_year(raw: &str) -> Result<u16, String> {
+    raw.parse::<u16>().map_err(|e| e.to_string())
+}
+
+fn parse_passport_id(raw: &str) -> Result<[u8; 9], String> {
+    let mut id = [0; 9];
+    if raw.len() != id.len() || !raw.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(format!("{} isn't 9 digits", raw));
+    }
+    for (digit, b) in id.iter_mut().zip(raw.bytes()) {
+        *digit = b - b'0';
+    }
+    Ok(id)
 }
 
-pub fn part_2() {
-    let data = parse();
+// A record the schema accepted, ready to be typed as a `Passport`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValidRecord<'a>(&'a RawRecord);
+
+impl<'a> ValidRecord<'a> {
+    pub fn record(&self) -> &'a RawRecord {
+        self.0
+    }
+}
 
-    let valid_passports = data
-        .into_iter()
-        .filter_map(|d| match d {
-            Identity::Passport(_) if d.is_valid() => Some(()),
-            _ => None,
+// Either the schema rejected a record, or it accepted one that a `Passport`
+// still can't hold, e.g. a custom schema that makes a field optional or
+// allows other units.
+#[derive(Clone, Debug, PartialEq)]
+pub enum PassportError {
+    Rejected(Diagnosis),
+    Missing(&'static str),
+    Invalid {
+        key: &'static str,
+        value: String,
+        reason: String,
+    },
+}
+
+impl fmt::Display for PassportError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PassportError::Rejected(diagnosis) => {
+                write!(f, "the schema rejects line {}", diagnosis.line)
+            }
+            PassportError::Missing(key) => write!(f, "a passport needs {}", key),
+            PassportError::Invalid { key, reason, .. } => write!(f, "{}: {}", key, reason),
+        }
+    }
+}
+
+fn typed<T>(
+    fields: &BTreeMap<String, String>,
+    key: &'static str,
+    parse: impl Fn(&str) -> Result<T, String>,
+) -> Result<T, PassportError> {
+    let value = fields.get(key).ok_or(PassportError::Missing(key))?;
+    parse(value).map_err(|reason| PassportError::Invalid {
+        key,
+        value: value.clone(),
+        reason,
+    })
+}
+
+impl TryFrom<ValidRecord<'_>> for Passport {
+    type Error = PassportError;
+    fn try_from(valid: ValidRecord) -> Result<Self, Self::Error> {
+        let fields = &valid.0.fields;
+
+        Ok(Passport {
+            birth_year: typed(fields, "byr", parse_year)?,
+            issue_year: typed(fields, "iyr", parse_year)?,
+            expiration_year: typed(fields, "eyr", parse_year)?,
+            height: typed(fields, "hgt", str::parse)?,
+            hair_color: typed(fields, "hcl", str::parse)?,
+            eye_color: typed(fields, "ecl", str::parse)?,
+            passport_id: typed(fields, "pid", parse_passport_id)?,
+            country_id: fields.get("cid").cloned(),
         })
-        .count();
+    }
+}
 
-    println!("Valid passport count {}", valid_passports);
+// Checks every record against `schema`, then types the ones it accepts.
+pub fn passports(records: &[RawRecord], schema: &Schema) -> Vec<Result<Passport, PassportError>> {
+    records
+        .iter()
+        .map(|record| {
+            let valid = schema.validate(record).map_err(PassportError::Rejected)?;
+            Passport::try_from(valid)
+        })
+        .collect()
+}
+
+impl Solution for Day4 {
+    const DAY: u16 = 4;
+
+    type Input = Vec<RawRecord>;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(raw: &str) -> error::Result<Self::Input> {
+        parse_str(raw)
+    }
+
+    fn part_1(data: &Self::Input) -> error::Result<Self::Part1> {
+        Ok(complete(data, default_schema()))
+    }
+
+    fn part_2(data: &Self::Input) -> error::Result<Self::Part2> {
+        Ok(valid(data, default_schema()))
+    }
+}
+
+fn complete(records: &[RawRecord], schema: &Schema) -> usize {
+    records
+        .iter()
+        .filter(|r| r.has_required_fields(schema))
+        .count()
+}
+
+// Counts what the schema accepts, so a custom schema's records count even
+// when they don't fit a `Passport`.
+fn valid(records: &[RawRecord], schema: &Schema) -> usize {
+    passports(records, schema)
+        .iter()
+        .filter(|p| !matches!(p, Err(PassportError::Rejected(_))))
+        .count()
+}
+
+// Day 4 checked against `schema` instead of the default.
+pub struct WithSchema(pub Schema);
+
+impl Runnable for WithSchema {
+    fn day(&self) -> u16 {
+        Day4::DAY
+    }
+
+    fn run(&self, part: u8, raw: &str) -> error::Result<Run> {
+        solution::timed(raw, parse_str, |records| match part {
+            1 => Ok(complete(records, &self.0).to_string()),
+            2 => Ok(valid(records, &self.0).to_string()),
+            x => Err(solution::invalid_part(Day4::DAY, x)),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -243,21 +682,285 @@ mod tests {
 
     #[test]
     fn parse() {
-        let data = super::parse_str(RAW_DATA);
-
-        assert!(match &data[0] {
-            super::Identity::Passport(_) => true,
-            x => panic!("Invalid variant {:?}", x),
-        });
-
-        assert!(match &data[1] {
-            super::Identity::Invalid(_) => true,
-            x => panic!("Invalid variant {:?}", x),
-        });
-
-        assert!(match &data[2] {
-            super::Identity::Passport(_) => true,
-            x => panic!("Invalid variant {:?}", x),
-        });
+        let data = super::parse_str(RAW_DATA).unwrap();
+
+        assert!(data[0].has_required_fields(super::default_schema()));
+        assert!(!data[1].has_required_fields(super::default_schema()));
+        assert!(data[2].has_required_fields(super::default_schema()));
+        assert_eq!(data[1].line, 4);
+        assert_eq!(data[1].fields["cid"], "350");
+    }
+
+    #[test]
+    fn parse_error() {
+        let err = super::parse_str("byr:1937\n\niyr:2013\necl:amb cid350").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "day 4 input, line 4 column 9: expected `key:value` [cid350]"
+        );
+    }
+
+    #[test]
+    fn default_schema() {
+        use super::{Day4, Solution};
+        use indoc::indoc;
+
+        let invalid = super::parse_str(indoc!(
+            "
+            eyr:1972 cid:100
+            hcl:#18171d ecl:amb hgt:170 pid:186cm iyr:2018 byr:1926
+
+            iyr:2019
+            hcl:#602927 eyr:1967 hgt:170cm
+            ecl:grn pid:012533040 byr:1946
+
+            hcl:dab227 iyr:2012
+            ecl:brn hgt:182cm pid:021572410 eyr:2020 byr:1992 cid:277
+
+            hgt:59cm ecl:zzz
+            eyr:2038 hcl:74454a iyr:2023
+            pid:3556412378 byr:2007"
+        ))
+        .unwrap();
+        let valid = super::parse_str(indoc!(
+            "
+            pid:087499704 hgt:74in ecl:grn iyr:2012 eyr:2030 byr:1980
+            hcl:#623a2f
+
+            eyr:2029 ecl:blu cid:129 byr:1989
+            iyr:2014 pid:896056539 hcl:#a97842 hgt:165cm
+
+            hcl:#888785
+            hgt:164cm byr:2001 iyr:2015 cid:88
+            pid:545766238 ecl:hzl
+            eyr:2022
+
+            iyr:2010 hgt:158cm hcl:#b6652a ecl:blu byr:1944 eyr:2021 pid:093154719"
+        ))
+        .unwrap();
+
+        assert_eq!(Day4::part_2(&invalid), Ok(0));
+        assert_eq!(Day4::part_2(&valid), Ok(4));
+        assert_eq!(Day4::part_1(&super::parse_str(RAW_DATA).unwrap()), Ok(2));
+    }
+
+    #[test]
+    fn custom_schema() {
+        use super::{Runnable, Schema};
+
+        let schema = indoc::indoc!(
+            r#"
+            [fields.hgt]
+            type = "unit"
+            units.m = { min = 1, max = 3 }
+
+            [fields.cid]
+            required = false
+            type = "int"
+            min = 1
+            max = 99
+            "#
+        )
+        .parse::<Schema>()
+        .unwrap();
+        let passport = |fields: &[(&str, &str)]| {
+            fields
+                .iter()
+                .map(|&(k, v)| (k.to_string(), v.to_string()))
+                .collect()
+        };
+
+        assert_eq!(schema.required().collect::<Vec<_>>(), vec!["hgt"]);
+        assert!(schema.is_valid(&passport(&[("hgt", "2m")])));
+        assert!(!schema.is_valid(&passport(&[("hgt", "2m"), ("cid", "100")])));
+        assert!(!schema.is_valid(&passport(&[("hgt", "2cm")])));
+        assert!(!schema.is_valid(&passport(&[("cid", "5")])));
+        assert_eq!(
+            schema.fields["hgt"].check("200cm"),
+            Err("200cm doesn't end in one of m".to_string())
+        );
+        assert_eq!(
+            schema.fields["cid"].check("100"),
+            Err("100 is outside 1-99".to_string())
+        );
+
+        assert!("[fields.byr]\ntype = \"int\"\nmin = 1"
+            .parse::<Schema>()
+            .is_err());
+        assert!("[fields.byr]\ntype = \"date\"".parse::<Schema>().is_err());
+        assert!("[fields.hcl]\ntype = \"regex\"\npattern = \"(\""
+            .parse::<Schema>()
+            .is_err());
+        assert!("[other]".parse::<Schema>().is_err());
+
+        // Both parts and the report go by the schema they're given.
+        let raw = "hgt:2m\n\nhgt:2cm cid:5\n\ncid:5";
+        let report = super::report(raw, &schema).unwrap();
+        assert_eq!(report.valid(), 1);
+        assert!(report.diagnoses[0].unknown.is_empty());
+        assert_eq!(
+            report.diagnoses[0].untyped.as_deref(),
+            Some("a passport needs byr")
+        );
+        assert_eq!(report.diagnoses[1].untyped, None);
+
+        let day4 = super::WithSchema(schema);
+        assert_eq!(day4.run(1, raw).unwrap().answer, "2");
+        assert_eq!(day4.run(2, raw).unwrap().answer, "1");
+        assert!(day4.run(3, raw).is_err());
+    }
+
+    #[test]
+    fn report() {
+        use super::InvalidField;
+
+        let report = super::report(
+            "byr:1937 iyr:2017 eyr:2020 hgt:183cm\nhcl:#fffffd ecl:gry pid:860033327\n\n\n\
+             iyr:2013 ecl:amb cid:350 eyr:2023\npid:28048884 hcl:#cfa07d byr:1929 hgt:6ft zip:1",
+            super::default_schema(),
+        )
+        .unwrap();
+        let second = &report.diagnoses[1];
+
+        assert_eq!(report.valid(), 1);
+        assert_eq!(report.diagnoses[0].line, 1);
+        assert_eq!(second.line, 5);
+        assert!(second.missing.is_empty());
+        assert_eq!(
+            second.invalid,
+            vec![
+                InvalidField {
+                    key: "hgt".to_string(),
+                    value: "6ft".to_string(),
+                    reason: "6ft doesn't end in one of cm, in".to_string(),
+                },
+                InvalidField {
+                    key: "pid".to_string(),
+                    value: "28048884".to_string(),
+                    reason: r"28048884 doesn't match ^\d{9}$".to_string(),
+                },
+            ]
+        );
+        assert_eq!(second.unknown, vec!["zip"]);
+        assert_eq!(
+            report.to_string(),
+            indoc::indoc!(
+                r"
+                1 of 2 passports are valid
+
+                line 1: valid
+
+                line 5: invalid
+                  hgt: 6ft doesn't end in one of cm, in
+                  pid: 28048884 doesn't match ^\d{9}$
+                  ignored unknown zip
+                "
+            )
+        );
+    }
+
+    #[test]
+    fn passport() {
+        use super::{EyeColor, Height, Passport, PassportError, Rgb, Schema};
+        use std::convert::TryFrom;
+
+        let data = super::parse_str(RAW_DATA).unwrap();
+        let schema = super::default_schema();
+        let passports = super::passports(&data, schema);
+        let passport = |i: usize| passports[i].clone();
+
+        assert_eq!(
+            passport(0).unwrap(),
+            Passport {
+                birth_year: 1937,
+                issue_year: 2017,
+                expiration_year: 2020,
+                height: Height::Cm(183),
+                hair_color: Rgb {
+                    red: 0xff,
+                    green: 0xff,
+                    blue: 0xfd
+                },
+                eye_color: EyeColor::Gray,
+                passport_id: [8, 6, 0, 0, 3, 3, 3, 2, 7],
+                country_id: Some("147".to_string()),
+            }
+        );
+        assert_eq!(passport(2).unwrap().height.to_string(), "179cm");
+        assert_eq!(
+            schema.validate(&data[3]).unwrap_err().missing,
+            vec!["byr".to_string()]
+        );
+
+        let diagnosis = match passport(1).unwrap_err() {
+            PassportError::Rejected(diagnosis) => diagnosis,
+            e => panic!("expected the schema to reject it, got {:?}", e),
+        };
+        assert_eq!(
+            (diagnosis.line, diagnosis.missing),
+            (4, vec!["hgt".to_string()])
+        );
+        assert_eq!(
+            passport(3).unwrap_err().to_string(),
+            "the schema rejects line 12"
+        );
+
+        // Looser rules than a passport can hold pass the schema but not the types.
+        let loose = "[fields.hgt]\n[fields.pid]\nrequired = false"
+            .parse::<Schema>()
+            .unwrap();
+        let record = |raw: &str| super::parse_str(raw).unwrap().remove(0);
+        let typed = |raw: &str| Passport::try_from(loose.validate(&record(raw)).unwrap());
+        assert_eq!(
+            typed("byr:1937 iyr:2017 eyr:2020 hgt:6ft hcl:#fffffd ecl:gry").unwrap_err(),
+            PassportError::Invalid {
+                key: "hgt",
+                value: "6ft".to_string(),
+                reason: "6ft isn't in cm or in".to_string(),
+            }
+        );
+        assert_eq!(
+            typed("byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd ecl:gry")
+                .unwrap_err()
+                .to_string(),
+            "a passport needs pid"
+        );
+
+        assert_eq!("70in".parse(), Ok(Height::In(70)));
+        assert_eq!(
+            "70".parse::<Height>(),
+            Err("70 isn't in cm or in".to_string())
+        );
+        assert_eq!("#0a0B0c".parse::<Rgb>().unwrap().to_string(), "#0a0b0c");
+        assert!("#+a0b0c".parse::<Rgb>().is_err());
+        assert_eq!("hzl".parse::<EyeColor>().unwrap().to_string(), "hzl");
+        assert_eq!(
+            super::parse_passport_id("12345678"),
+            Err("12345678 isn't 9 digits".to_string())
+        );
+    }
+
+    #[test]
+    fn blank_lines() {
+        let passport = "byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd ecl:gry pid:860033327";
+        let raw = format!("\n{}\n\n\n\n{}\n\n\n{}\n\n", passport, passport, passport);
+        let report = super::report(&raw, super::default_schema()).unwrap();
+
+        assert_eq!(
+            report
+                .diagnoses
+                .iter()
+                .map(|d| (d.line, d.is_valid()))
+                .collect::<Vec<_>>(),
+            vec![(2, true), (6, true), (9, true)]
+        );
+        assert_eq!(
+            super::parse_str("byr:1937\n\n\n\niyr:2013 ecl")
+                .unwrap_err()
+                .to_string(),
+            "day 4 input, line 5 column 10: expected `key:value` [ecl]"
+        );
+        assert!(super::parse_str("\n\n").unwrap().is_empty());
     }
 }
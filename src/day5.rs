@@ -1,34 +1,48 @@
-use std::convert::Infallible;
-use std::fs;
+use std::fmt;
 use std::str::FromStr;
 
-pub const DAY: u16 = 5;
+use crate::error::{first_column, Error, Result};
+use crate::solution::Solution;
+
+pub struct Day5;
 
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
-struct Seat {
-    row: usize,
-    col: usize,
+pub struct Seat {
+    pub row: usize,
+    pub col: usize,
 }
 
 impl Seat {
-    fn id(&self) -> usize {
+    pub fn id(&self) -> usize {
         (self.row * 8) + self.col
     }
 }
 
 impl FromStr for Seat {
-    type Err = Infallible;
-    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+    type Err = Error;
+    fn from_str(raw: &str) -> Result<Self> {
         let mut col = 0;
         let mut row = 0;
 
         let as_chars = raw.chars().collect::<Vec<_>>();
 
+        if as_chars.len() != 10 {
+            return Err(Error::parse(
+                Day5::DAY,
+                raw,
+                "expected 7 `F`/`B` followed by 3 `L`/`R`",
+            ));
+        }
+
+        let unexpected = |i: usize, expected: &str| {
+            Err(Error::parse(Day5::DAY, raw, format!("expected {}", expected)).at(1, i + 1))
+        };
+
         for (i, c) in as_chars.iter().enumerate().take(7) {
             match c {
                 'F' => (),
                 'B' => row |= 1 << (6 - i),
-                _ => (),
+                _ => return unexpected(i, "`F` or `B`"),
             }
         }
 
@@ -36,7 +50,7 @@ impl FromStr for Seat {
             match c {
                 'R' => col |= 1 << (2 - i),
                 'L' => (),
-                _ => (),
+                _ => return unexpected(i + 7, "`L` or `R`"),
             }
         }
 
@@ -44,35 +58,61 @@ impl FromStr for Seat {
     }
 }
 
-fn parse() -> Vec<Seat> {
-    let raw = fs::read_to_string("./src/day5.txt").unwrap();
-    raw.lines().map(|l| l.parse::<Seat>().unwrap()).collect()
+// The free seat with taken seats on both sides of it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MySeat {
+    Found(usize),
+    NoSolution,
 }
 
-pub fn part_1() {
-    let data = parse();
-
-    let max_id = data.into_iter().max_by(|x, y| x.id().cmp(&y.id())).unwrap();
-    println!("The max seat id is {}", max_id.id());
+impl fmt::Display for MySeat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MySeat::Found(id) => write!(f, "{}", id),
+            MySeat::NoSolution => write!(f, "no solution: no free seat between two taken ones"),
+        }
+    }
 }
 
-pub fn part_2() {
-    let mut raw = parse();
-    raw.sort();
-    let data = &raw[1..raw.len() - 1];
-    let your_seat = (*data)
-        .windows(2)
-        .find(|slice| match slice {
-            [x, y] => x.id() + 2 == y.id(),
-            _ => false,
-        })
-        .map(|slice| match slice {
-            [x, _] => Some(x.id() + 1),
-            _ => None,
-        })
-        .flatten()
-        .unwrap();
-    println!("Your seat id is {}", your_seat)
+impl Solution for Day5 {
+    const DAY: u16 = 5;
+
+    type Input = Vec<Seat>;
+    type Part1 = usize;
+    type Part2 = MySeat;
+
+    fn parse(raw: &str) -> Result<Self::Input> {
+        let seats = raw
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| {
+                l.trim()
+                    .parse::<Seat>()
+                    .map_err(|e| e.offset_line(i).offset_column(first_column(l) - 1))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        if seats.is_empty() {
+            return Err(Error::Empty { day: Self::DAY });
+        }
+
+        Ok(seats)
+    }
+
+    fn part_1(data: &Self::Input) -> Result<Self::Part1> {
+        // `parse` refuses empty input.
+        Ok(data.iter().map(|seat| seat.id()).max().unwrap())
+    }
+
+    fn part_2(data: &Self::Input) -> Result<Self::Part2> {
+        let mut ids = data.iter().map(Seat::id).collect::<Vec<_>>();
+        ids.sort_unstable();
+        Ok(ids
+            .windows(2)
+            .find(|pair| pair[0] + 2 == pair[1])
+            .map_or(MySeat::NoSolution, |pair| MySeat::Found(pair[0] + 1)))
+    }
 }
 
 #[cfg(test)]
@@ -84,4 +124,34 @@ mod tests {
         assert_eq!(data, super::Seat { row: 70, col: 7 });
         assert_eq!(data.id(), 567);
     }
+
+    #[test]
+    fn parse_error() {
+        let err = "BFFFBBFRLX".parse::<super::Seat>().unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "day 5 input, line 1 column 10: expected `L` or `R` [BFFFBBFRLX]"
+        );
+        assert!("BFFFBBFRR".parse::<super::Seat>().is_err());
+    }
+
+    #[test]
+    fn part_2() {
+        use super::{Day5, MySeat, Solution};
+
+        let seats = |raw: &str| Day5::parse(raw).unwrap();
+
+        assert_eq!(
+            Day5::part_2(&seats("FFFFFFFLLL\nFFFFFFFLLR\nFFFFFFFLRR")),
+            Ok(MySeat::Found(2))
+        );
+        assert_eq!(Day5::part_2(&seats("FFFFFFFLLL")), Ok(MySeat::NoSolution));
+        assert_eq!(
+            Day5::part_2(&seats("FFFFFFFLLL\nFFFFFFFLLR"))
+                .unwrap()
+                .to_string(),
+            "no solution: no free seat between two taken ones"
+        );
+    }
 }
@@ -1,48 +1,62 @@
 use std::collections::BTreeSet;
-use std::fs;
 
-pub const DAY: u16 = 6;
+use crate::error::{first_column, Error, Result};
+use crate::solution::Solution;
 
-fn parse_str(raw: &str) -> Vec<BTreeSet<char>> {
-    raw.split("\n\n")
-        .into_iter()
-        .map(|group| group.lines().flat_map(|form| form.chars()).collect())
+pub struct Day6;
+
+// One answer set per group member.
+type Group = Vec<BTreeSet<char>>;
+
+fn parse_form(form: &str) -> Result<BTreeSet<char>> {
+    form.trim()
+        .chars()
+        .enumerate()
+        .map(|(i, c)| match c {
+            'a'..='z' => Ok(c),
+            _ => Err(
+                Error::parse(Day6::DAY, form.trim(), "expected answers `a` to `z`")
+                    .at(1, first_column(form) + i),
+            ),
+        })
         .collect()
 }
 
-fn parse_str_2(raw: &str) -> Vec<BTreeSet<char>> {
-    raw.split("\n\n")
-        .into_iter()
-        .map(|group| {
-            let group_members = group
+fn parse_groups(raw: &str) -> Result<Vec<Group>> {
+    let mut line = 0;
+    let mut groups = vec![];
+
+    for group in raw.split("\n\n") {
+        groups.push(
+            group
                 .lines()
-                .map(|form| form.chars().collect::<BTreeSet<_>>())
-                .collect::<Vec<_>>();
-            let n = group_members.len();
-            match n {
-                0 => BTreeSet::new(),
-                _ => {
-                    let mut acc = group_members
-                        .first()
-                        .unwrap()
-                        .iter()
-                        .cloned()
-                        .collect::<BTreeSet<_>>();
-
-                    match n {
-                        1 => acc,
-                        _ => {
-                            let rest = &group_members[1..];
-
-                            for curr in rest {
-                                acc = acc.intersection(&curr).copied().collect();
-                            }
-
-                            acc
-                        }
-                    }
-                }
-            }
+                .enumerate()
+                .map(|(i, form)| parse_form(form).map_err(|e| e.offset_line(line + i)))
+                .collect::<Result<Group>>()?,
+        );
+        line += group.matches('\n').count() + 2;
+    }
+
+    Ok(groups)
+}
+
+// Questions anyone in the group answered "yes" to.
+fn anyone(groups: &[Group]) -> Vec<BTreeSet<char>> {
+    groups
+        .iter()
+        .map(|members| members.iter().flatten().copied().collect())
+        .collect()
+}
+
+// Questions everyone in the group answered "yes" to.
+fn everyone(groups: &[Group]) -> Vec<BTreeSet<char>> {
+    groups
+        .iter()
+        .map(|members| match members.split_first() {
+            None => BTreeSet::new(),
+            Some((first, rest)) => rest.iter().fold(first.clone(), |acc, curr| {
+                acc.intersection(curr).copied().collect()
+            }),
         })
         .collect()
 }
@@ -51,34 +65,24 @@ fn count_set_cardinality<T>(s: Vec<BTreeSet<T>>) -> usize {
     s.into_iter().map(|group| group.len()).sum()
 }
 
-fn parse() -> Vec<BTreeSet<char>> {
-    let raw = fs::read_to_string("./src/day6.txt").unwrap();
-    parse_str(&raw)
-}
-
-fn parse_2() -> Vec<BTreeSet<char>> {
-    let raw = fs::read_to_string("./src/day6.txt").unwrap();
-    parse_str_2(&raw)
-}
+impl Solution for Day6 {
+    const DAY: u16 = 6;
 
-pub fn part_1() {
-    let data = parse();
-    let counts: usize = count_set_cardinality(data);
+    type Input = Vec<Group>;
+    type Part1 = usize;
+    type Part2 = usize;
 
-    println!(
-        "There are {} distinct answers from the groups collectively",
-        counts
-    )
-}
+    fn parse(raw: &str) -> Result<Self::Input> {
+        parse_groups(raw)
+    }
 
-pub fn part_2() {
-    let data = parse_2();
-    let counts: usize = count_set_cardinality(data);
+    fn part_1(groups: &Self::Input) -> Result<Self::Part1> {
+        Ok(count_set_cardinality(anyone(groups)))
+    }
 
-    println!(
-        "There are {} unanimous distinct answers from the groups collectively",
-        counts
-    )
+    fn part_2(groups: &Self::Input) -> Result<Self::Part2> {
+        Ok(count_set_cardinality(everyone(groups)))
+    }
 }
 
 #[cfg(test)]
@@ -105,16 +109,26 @@ mod tests {
 
     #[test]
     fn parse() {
-        let data = super::parse_str(RAW_DATA);
+        let data = super::anyone(&super::parse_groups(RAW_DATA).unwrap());
 
         assert_eq!(data[1], btreeset!('a', 'b', 'c'));
     }
 
     #[test]
     fn parse_2() {
-        let data = super::parse_str_2(RAW_DATA);
+        let data = super::everyone(&super::parse_groups(RAW_DATA).unwrap());
         let counts = super::count_set_cardinality(data);
 
         assert_eq!(counts, 6);
     }
+
+    #[test]
+    fn parse_error() {
+        let err = super::parse_groups("abc\n\na\nb?").unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "day 6 input, line 4 column 2: expected answers `a` to `z` [b?]"
+        );
+    }
 }
@@ -1,16 +1,17 @@
 use std::collections::BTreeMap;
-use std::convert::Infallible;
-use std::fs;
 use std::str::FromStr;
 
 use regex::Regex;
 
-pub const DAY: u16 = 7;
+use crate::error::{first_column, Error, Result};
+use crate::solution::Solution;
+
+pub struct Day7;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
-struct Rule {
-    name: String,
-    count_by_child: BTreeMap<String, usize>,
+pub struct Rule {
+    pub name: String,
+    pub count_by_child: BTreeMap<String, usize>,
 }
 
 fn normalize_bag_name(s: &str) -> String {
@@ -18,14 +19,22 @@ fn normalize_bag_name(s: &str) -> String {
 }
 
 impl FromStr for Rule {
-    type Err = Infallible;
-    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+    type Err = Error;
+    fn from_str(raw: &str) -> Result<Self> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"^(\d+) (.*)$",).unwrap();
         }
         let split = raw.split(" contain ").collect::<Vec<_>>();
+        if split.len() != 2 {
+            return Err(Error::parse(
+                Day7::DAY,
+                raw,
+                "expected `<bag> contain <bags>.`",
+            ));
+        }
         let name = normalize_bag_name(split[0]);
         let mut count_by_child = btreemap! {};
+        let mut column = split[0].len() + " contain ".len() + 1;
         for s in split[1].trim_end_matches('.').split(", ") {
             if s == "no other bags" {
                 return Ok(Rule {
@@ -33,9 +42,14 @@ impl FromStr for Rule {
                     count_by_child: btreemap! {},
                 });
             }
-            let captures = RE.captures(s).unwrap();
-            let count = captures[1].parse::<usize>().unwrap();
+            let captures = RE.captures(s).ok_or_else(|| {
+                Error::parse(Day7::DAY, s, "expected `<count> <bag>`").at(1, column)
+            })?;
+            let count = captures[1]
+                .parse::<usize>()
+                .map_err(|e| Error::parse(Day7::DAY, s, e.to_string()).at(1, column))?;
             let child_name = normalize_bag_name(&captures[2]);
+            column += s.len() + ", ".len();
 
             count_by_child.insert(child_name.to_string(), count);
         }
@@ -47,24 +61,26 @@ impl FromStr for Rule {
     }
 }
 
-type FlattenedGraph = BTreeMap<String, BTreeMap<String, usize>>;
+pub type FlattenedGraph = BTreeMap<String, BTreeMap<String, usize>>;
 
-fn parse_str(raw: &str) -> FlattenedGraph {
+fn parse_str(raw: &str) -> Result<FlattenedGraph> {
     raw.lines()
-        .map(|l| {
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
             let Rule {
                 name,
                 count_by_child,
-            } = l.parse::<Rule>().unwrap();
-            (name, count_by_child)
+            } = l
+                .trim()
+                .parse::<Rule>()
+                .map_err(|e| e.offset_line(i).offset_column(first_column(l) - 1))?;
+            Ok((name, count_by_child))
         })
         .collect()
 }
 
-pub fn part_1() {
-    let raw = fs::read_to_string("./src/day7.txt").unwrap();
-    let rule_map = parse_str(&raw);
-
+fn count_containing(rule_map: &FlattenedGraph) -> usize {
     let mut new_additions = btreeset! {"shiny gold".to_string()};
     let mut result = btreeset! {};
 
@@ -100,10 +116,7 @@ pub fn part_1() {
         new_additions = newer_additions.clone();
     }
 
-    println!(
-        "There are {} potentially encompassing bag colors",
-        result.len(),
-    );
+    result.len()
 }
 
 fn count_contained_helper(g: &FlattenedGraph, counting: String) -> usize {
@@ -115,17 +128,28 @@ fn count_contained_helper(g: &FlattenedGraph, counting: String) -> usize {
         .sum::<usize>()
 }
 
-fn count_contained(raw: &str) -> usize {
-    let rule_map = parse_str(&raw);
-
-    count_contained_helper(&rule_map, "shiny gold".to_string())
+fn count_contained(rule_map: &FlattenedGraph) -> usize {
+    count_contained_helper(rule_map, "shiny gold".to_string())
 }
 
-pub fn part_2() {
-    let raw = fs::read_to_string("./src/day7.txt").unwrap();
-    let result = count_contained(&raw);
+impl Solution for Day7 {
+    const DAY: u16 = 7;
+
+    type Input = FlattenedGraph;
+    type Part1 = usize;
+    type Part2 = usize;
+
+    fn parse(raw: &str) -> Result<Self::Input> {
+        parse_str(raw)
+    }
+
+    fn part_1(rule_map: &Self::Input) -> Result<Self::Part1> {
+        Ok(count_containing(rule_map))
+    }
 
-    println!("There are {} potentially encompassed bags", result,);
+    fn part_2(rule_map: &Self::Input) -> Result<Self::Part2> {
+        Ok(count_contained(rule_map))
+    }
 }
 
 #[cfg(test)]
@@ -154,21 +178,36 @@ dotted black bags contain no other bags."
         assert_eq!(parsed[0].count_by_child["bright white"], 1);
     }
 
+    #[test]
+    fn parse_error() {
+        let err =
+            super::parse_str("light red bags contain 1 bright white bag, two muted yellow bags.")
+                .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "day 7 input, line 1 column 44: expected `<count> <bag>` [two muted yellow bags]"
+        );
+    }
+
     #[test]
     fn count_contained() {
-        let counted = super::count_contained(RAW_DATA);
+        let counted = super::count_contained(&super::parse_str(RAW_DATA).unwrap());
 
         assert_eq!(counted, 32);
 
-        let counted_2 = super::count_contained(indoc!(
-            "shiny gold bags contain 2 dark red bags.
+        let counted_2 = super::count_contained(
+            &super::parse_str(indoc!(
+                "shiny gold bags contain 2 dark red bags.
             dark red bags contain 2 dark orange bags.
             dark orange bags contain 2 dark yellow bags.
             dark yellow bags contain 2 dark green bags.
             dark green bags contain 2 dark blue bags.
             dark blue bags contain 2 dark violet bags.
             dark violet bags contain no other bags."
-        ));
+            ))
+            .unwrap(),
+        );
 
         assert_eq!(counted_2, 126);
     }
@@ -1,37 +1,44 @@
 use std::collections::HashSet;
-use std::convert::Infallible;
-use std::fs;
+use std::fmt;
 use std::str::FromStr;
 
 use regex::Regex;
 
-pub const DAY: u16 = 8;
+use crate::error::{first_column, Error, Result};
+use crate::solution::Solution;
+
+pub struct Day8;
 
 // acc increases or decreases a single global value called the accumulator by the value given in the argument. For example, acc +7 would increase the accumulator by 7. The accumulator starts at 0. After an acc instruction, the instruction immediately below it is executed next.
 // jmp jumps to a new instruction relative to itself. The next instruction to execute is found using the argument as an offset from the jmp instruction; for example, jmp +2 would skip the next instruction, jmp +1 would continue to the instruction immediately below it, and jmp -20 would cause the instruction 20 lines above to be executed next.
 // nop stands for No OPeration - it does nothing. The instruction immediately below it is executed next.
 
 #[derive(Clone, Debug, Eq, PartialEq)]
-enum OpCode {
+pub enum OpCode {
     Acc(i32),
     Jmp(i32),
     Nop(i32),
 }
 
 impl FromStr for OpCode {
-    type Err = Infallible;
-    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+    type Err = Error;
+    fn from_str(raw: &str) -> Result<Self> {
         lazy_static! {
             static ref RE: Regex = Regex::new(r"^(\w+) ([-+]\d+)$",).unwrap();
         }
-        let captures = RE.captures(raw).unwrap();
-        let by = captures[2].parse::<i32>().unwrap();
+        let captures = RE
+            .captures(raw)
+            .ok_or_else(|| Error::parse(Day8::DAY, raw, "expected `<op> <+/-offset>`"))?;
+        let by_match = captures.get(2).unwrap();
+        let by = by_match.as_str().parse::<i32>().map_err(|e| {
+            Error::parse(Day8::DAY, by_match.as_str(), e.to_string()).at(1, by_match.start() + 1)
+        })?;
 
         let op = match &captures[1] {
             "acc" => OpCode::Acc(by),
             "jmp" => OpCode::Jmp(by),
             "nop" => OpCode::Nop(by),
-            x => panic!("[{}] not a valid op code", x),
+            x => return Err(Error::parse(Day8::DAY, x, "not a valid op code")),
         };
 
         Ok(op)
@@ -39,13 +46,24 @@ impl FromStr for OpCode {
 }
 
 #[derive(Clone, Debug)]
-struct Program {
-    instructions: Vec<OpCode>,
-    accumulator: i32,
-    current_instruction: usize,
+pub struct Program {
+    pub instructions: Vec<OpCode>,
+    pub accumulator: i32,
+    pub current_instruction: usize,
     previously_run_lines: HashSet<usize>,
 }
 
+impl Program {
+    pub fn new(instructions: Vec<OpCode>) -> Program {
+        Program {
+            accumulator: 0,
+            current_instruction: 0,
+            instructions,
+            previously_run_lines: hashset! {},
+        }
+    }
+}
+
 impl Iterator for Program {
     type Item = usize;
 
@@ -80,15 +98,14 @@ impl Iterator for Program {
                 self.current_instruction += 1;
             }
             OpCode::Jmp(by) => {
-                if by.is_negative() {
-                    self.current_instruction = current_instruction
-                        .checked_sub(by.wrapping_abs() as usize)
-                        .unwrap();
+                // Jumping before the first instruction leaves the program,
+                // like jumping past the last one, but never counts as finishing.
+                self.current_instruction = if by.is_negative() {
+                    current_instruction.checked_sub(by.unsigned_abs() as usize)
                 } else {
-                    self.current_instruction = current_instruction
-                        .checked_add(by.wrapping_abs() as usize)
-                        .unwrap();
+                    current_instruction.checked_add(by as usize)
                 }
+                .unwrap_or(usize::MAX);
             }
             OpCode::Nop(_) => {
                 self.current_instruction += 1;
@@ -99,35 +116,48 @@ impl Iterator for Program {
     }
 }
 
-fn parse_str(raw: &str) -> Program {
-    let instructions = raw.lines().map(|l| l.parse::<OpCode>().unwrap()).collect();
+fn parse_str(raw: &str) -> Result<Program> {
+    let instructions = raw
+        .lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            l.trim()
+                .parse::<OpCode>()
+                .map_err(|e| e.offset_line(i).offset_column(first_column(l) - 1))
+        })
+        .collect::<Result<_>>()?;
+
+    Ok(Program::new(instructions))
+}
 
-    Program {
-        accumulator: 0,
-        current_instruction: 0,
-        instructions,
-        previously_run_lines: hashset! {},
-    }
+// Runs until the program either terminates or is about to repeat an instruction.
+pub fn accumulator_before_loop(program: &Program) -> i32 {
+    let mut program = program.clone();
+    while program.next().is_some() {}
+    program.accumulator
 }
 
-pub fn part_1() {
-    let raw = fs::read_to_string("./src/day8.txt").unwrap();
-    let program = parse_str(&raw);
+#[derive(Clone, Debug, PartialEq)]
+pub struct Repair {
+    // `None` when the program already terminated without any changes.
+    pub swapped: Option<(usize, OpCode)>,
+    pub accumulator: i32,
+}
 
-    for acc in program.clone() {
-        println!(
-            "{:?} | {}",
-            program.instructions[program.current_instruction], acc
-        )
+impl fmt::Display for Repair {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.accumulator)
     }
 }
 
-fn bang_on_it_till_it_works(program: Program) -> Option<i32> {
+pub fn bang_on_it_till_it_works(program: Program) -> Option<Repair> {
     let original_program = program.clone();
     if program.clone().last() == Some(program.instructions.len()) {
-        println!("No changes required");
-
-        return None;
+        return Some(Repair {
+            swapped: None,
+            accumulator: accumulator_before_loop(&program),
+        });
     }
 
     let indicies_to_toggle = program
@@ -148,19 +178,37 @@ fn bang_on_it_till_it_works(program: Program) -> Option<i32> {
         current.instructions[index] = tweak.clone();
 
         if current.clone().last() == Some(target_index) {
-            while current.next().is_some() {}
-            dbg!("Successful run with", current.accumulator, index, tweak);
-            return Some(current.accumulator);
+            return Some(Repair {
+                swapped: Some((index, tweak)),
+                accumulator: accumulator_before_loop(&current),
+            });
         }
     }
 
     None
 }
 
-pub fn part_2() {
-    let raw = fs::read_to_string("./src/day8.txt").unwrap();
-    let program = parse_str(&raw);
-    bang_on_it_till_it_works(program);
+impl Solution for Day8 {
+    const DAY: u16 = 8;
+
+    type Input = Program;
+    type Part1 = i32;
+    type Part2 = Repair;
+
+    fn parse(raw: &str) -> Result<Self::Input> {
+        parse_str(raw)
+    }
+
+    fn part_1(program: &Self::Input) -> Result<Self::Part1> {
+        Ok(accumulator_before_loop(program))
+    }
+
+    fn part_2(program: &Self::Input) -> Result<Self::Part2> {
+        bang_on_it_till_it_works(program.clone()).ok_or_else(|| Error::NoAnswer {
+            day: Self::DAY,
+            reason: "no single jmp/nop swap lets the program finish".to_string(),
+        })
+    }
 }
 
 #[cfg(test)]
@@ -180,7 +228,7 @@ mod tests {
     );
     #[test]
     fn parse() {
-        let parsed = super::parse_str(RAW_DATA);
+        let parsed = super::parse_str(RAW_DATA).unwrap();
 
         for instruction_index in parsed.clone() {
             println!(
@@ -194,9 +242,38 @@ mod tests {
 
     #[test]
     fn part_2() {
-        let parsed = super::parse_str(RAW_DATA);
+        let parsed = super::parse_str(RAW_DATA).unwrap();
 
         let result = super::bang_on_it_till_it_works(parsed).unwrap();
-        assert_eq!(result, 8);
+        assert_eq!(result.accumulator, 8);
+        assert_eq!(result.swapped, Some((7, super::OpCode::Nop(-4))));
+    }
+
+    #[test]
+    fn parse_error() {
+        let err = super::parse_str("nop +0\nmul +1").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 8 input, line 2 column 1: not a valid op code [mul]"
+        );
+
+        let err = super::parse_str("jmp 4").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "day 8 input, line 1 column 1: expected `<op> <+/-offset>` [jmp 4]"
+        );
+    }
+
+    #[test]
+    fn no_answer() {
+        use super::{Day8, Solution};
+
+        // Every swap still loops or jumps off the front.
+        let program = super::parse_str("jmp -1\nacc +2\njmp -1").unwrap();
+        assert_eq!(Day8::part_1(&program), Ok(0));
+        assert_eq!(
+            Day8::part_2(&program).unwrap_err().to_string(),
+            "day 8 has no answer: no single jmp/nop swap lets the program finish"
+        );
     }
 }
@@ -1,13 +1,23 @@
 use std::collections::BTreeSet;
-use std::fs;
 
-pub const DAY: u16 = 9;
+use crate::error::{first_column, Error, Result};
+use crate::solution::Solution;
 
-fn parse_str(raw: &str) -> Vec<u128> {
-    raw.lines().map(|l| l.parse::<u128>().unwrap()).collect()
+pub struct Day9;
+
+fn parse_str(raw: &str) -> Result<Vec<u128>> {
+    raw.lines()
+        .enumerate()
+        .filter(|(_, l)| !l.trim().is_empty())
+        .map(|(i, l)| {
+            l.trim().parse::<u128>().map_err(|e| {
+                Error::parse(Day9::DAY, l.trim(), e.to_string()).at(i + 1, first_column(l))
+            })
+        })
+        .collect()
 }
 
-fn first_not_sum_of_preable(preable: usize, candidates: Vec<u128>) -> Option<u128> {
+fn first_not_sum_of_preable(preable: usize, candidates: &[u128]) -> Option<u128> {
     for window in candidates.windows(preable + 1) {
         let preable_nums = window[..=preable].iter().copied().collect::<BTreeSet<_>>();
         let current = window[preable];
@@ -30,15 +40,7 @@ fn first_not_sum_of_preable(preable: usize, candidates: Vec<u128>) -> Option<u12
     None
 }
 
-pub fn part_1() {
-    let raw = fs::read_to_string("./src/day9.txt").unwrap();
-    let parsed = parse_str(&raw);
-    let result = first_not_sum_of_preable(25, parsed);
-
-    dbg!(result);
-}
-
-fn find_continuous_number_set(matching: u128, candidates: Vec<u128>) -> Option<(u128, u128)> {
+fn find_continuous_number_set(matching: u128, candidates: &[u128]) -> Option<(u128, u128)> {
     for preable in 2..=candidates.len() {
         for window in candidates.windows(preable) {
             let preable_nums = window.iter().copied().collect::<BTreeSet<_>>();
@@ -55,13 +57,43 @@ fn find_continuous_number_set(matching: u128, candidates: Vec<u128>) -> Option<(
     None
 }
 
-pub fn part_2() {
-    let raw = fs::read_to_string("./src/day9.txt").unwrap();
-    let parsed = parse_str(&raw);
+// Numbers each one has to be a sum of two of.
+const PREAMBLE: usize = 25;
+
+impl Solution for Day9 {
+    const DAY: u16 = 9;
 
-    let (fst, snd) = find_continuous_number_set(88311122, parsed).unwrap();
+    type Input = Vec<u128>;
+    type Part1 = u128;
+    type Part2 = u128;
+
+    fn parse(raw: &str) -> Result<Self::Input> {
+        parse_str(raw)
+    }
 
-    dbg!(fst + snd);
+    fn part_1(parsed: &Self::Input) -> Result<Self::Part1> {
+        let reason = if parsed.len() <= PREAMBLE {
+            format!("only {} numbers, all of them preamble", parsed.len())
+        } else {
+            format!("every number is a sum of two of the {} before it", PREAMBLE)
+        };
+
+        first_not_sum_of_preable(PREAMBLE, parsed).ok_or(Error::NoAnswer {
+            day: Self::DAY,
+            reason,
+        })
+    }
+
+    fn part_2(parsed: &Self::Input) -> Result<Self::Part2> {
+        let invalid = Self::part_1(parsed)?;
+        let (fst, snd) =
+            find_continuous_number_set(invalid, parsed).ok_or_else(|| Error::NoAnswer {
+                day: Self::DAY,
+                reason: format!("no run of 2 or more numbers sums to {}", invalid),
+            })?;
+
+        Ok(fst + snd)
+    }
 }
 
 #[cfg(test)]
@@ -92,17 +124,34 @@ mod tests {
     );
     #[test]
     fn first_not_sum_of_preable() {
-        let parsed = super::parse_str(RAW_DATA);
-        let result = super::first_not_sum_of_preable(5, parsed);
+        let parsed = super::parse_str(RAW_DATA).unwrap();
+        let result = super::first_not_sum_of_preable(5, &parsed);
 
         assert_eq!(result, Some(127))
     }
 
     #[test]
     fn find_continuous_number_set() {
-        let parsed = super::parse_str(RAW_DATA);
-        let result = super::find_continuous_number_set(127, parsed);
+        let parsed = super::parse_str(RAW_DATA).unwrap();
+        let result = super::find_continuous_number_set(127, &parsed);
 
         assert_eq!(result, Some((15, 47)))
     }
+
+    #[test]
+    fn no_answer() {
+        use super::{Day9, Solution};
+
+        let short = super::parse_str(RAW_DATA).unwrap();
+        assert_eq!(
+            Day9::part_1(&short).unwrap_err().to_string(),
+            "day 9 has no answer: only 20 numbers, all of them preamble"
+        );
+
+        let sums = (1..=30).collect::<Vec<u128>>();
+        assert_eq!(
+            Day9::part_2(&sums).unwrap_err().to_string(),
+            "day 9 has no answer: every number is a sum of two of the 25 before it"
+        );
+    }
 }
@@ -0,0 +1,127 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Error {
+    // Line and column are 1-based. Parsers for a single line or record report
+    // positions relative to that text, and `parse_str` shifts them into place.
+    Parse {
+        day: u16,
+        line: usize,
+        column: usize,
+        text: String,
+        reason: String,
+    },
+    Empty {
+        day: u16,
+    },
+    // Input streamed from a reader failed partway through.
+    Read {
+        day: u16,
+        line: usize,
+        reason: String,
+    },
+    // Asked for something the solver can't do, regardless of the input.
+    Unsupported {
+        day: u16,
+        reason: String,
+    },
+    // The input parsed, but the puzzle has no answer for it.
+    NoAnswer {
+        day: u16,
+        reason: String,
+    },
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn parse(day: u16, text: &str, reason: impl Into<String>) -> Error {
+        Error::Parse {
+            day,
+            line: 1,
+            column: 1,
+            text: text.to_string(),
+            reason: reason.into(),
+        }
+    }
+
+    pub fn at(self, line: usize, column: usize) -> Error {
+        match self {
+            Error::Parse {
+                day, text, reason, ..
+            } => Error::Parse {
+                day,
+                line,
+                column,
+                text,
+                reason,
+            },
+            e => e,
+        }
+    }
+
+    pub fn offset_line(self, by: usize) -> Error {
+        match self {
+            Error::Parse { line, column, .. } => self.at(line + by, column),
+            e => e,
+        }
+    }
+
+    pub fn offset_column(self, by: usize) -> Error {
+        match self {
+            Error::Parse { line, column, .. } => self.at(line, column + by),
+            e => e,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Parse {
+                day,
+                line,
+                column,
+                text,
+                reason,
+            } => write!(
+                f,
+                "day {} input, line {} column {}: {} [{}]",
+                day, line, column, reason, text
+            ),
+            Error::Empty { day } => write!(f, "day {} input is empty", day),
+            Error::Read { day, line, reason } => write!(
+                f,
+                "day {} input, line {}: couldn't read: {}",
+                day, line, reason
+            ),
+            Error::Unsupported { day, reason } => write!(f, "day {}: {}", day, reason),
+            Error::NoAnswer { day, reason } => write!(f, "day {} has no answer: {}", day, reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+// 1-based column of the first non-whitespace character.
+pub fn first_column(line: &str) -> usize {
+    line.len() - line.trim_start().len() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn positions() {
+        let e = Error::parse(8, "mul +1", "not a valid op code")
+            .offset_column(4)
+            .offset_line(2);
+
+        assert_eq!(
+            e.to_string(),
+            "day 8 input, line 3 column 5: not a valid op code [mul +1]"
+        );
+        assert_eq!(first_column("    42"), 5);
+    }
+}
@@ -0,0 +1,347 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::{Index, IndexMut};
+
+use crate::error::{first_column, Error, Result};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, PartialOrd, Ord)]
+pub struct Spot {
+    pub row: usize,
+    pub col: usize,
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Dir {
+    Up,
+    RightAndUp,
+    Right,
+    RightAndDown,
+    Down,
+    LeftAndDown,
+    Left,
+    LeftAndUp,
+}
+
+impl Dir {
+    pub const ALL: [Dir; 8] = [
+        Dir::Up,
+        Dir::RightAndUp,
+        Dir::Right,
+        Dir::RightAndDown,
+        Dir::Down,
+        Dir::LeftAndDown,
+        Dir::Left,
+        Dir::LeftAndUp,
+    ];
+
+    pub const CARDINAL: [Dir; 4] = [Dir::Up, Dir::Right, Dir::Down, Dir::Left];
+
+    // (rows, cols) moved by one step.
+    pub fn offset(self) -> (isize, isize) {
+        match self {
+            Dir::Up => (-1, 0),
+            Dir::RightAndUp => (-1, 1),
+            Dir::Right => (0, 1),
+            Dir::RightAndDown => (1, 1),
+            Dir::Down => (1, 0),
+            Dir::LeftAndDown => (1, -1),
+            Dir::Left => (0, -1),
+            Dir::LeftAndUp => (-1, -1),
+        }
+    }
+}
+
+impl Spot {
+    // Only refuses to go below zero, the grid checks the far edges.
+    pub fn look(&self, direction: Dir) -> Option<Spot> {
+        let (rows, cols) = direction.offset();
+
+        Some(Spot {
+            row: checked_add(self.row, rows)?,
+            col: checked_add(self.col, cols)?,
+        })
+    }
+}
+
+fn checked_add(n: usize, by: isize) -> Option<usize> {
+    if by < 0 {
+        n.checked_sub(by.unsigned_abs())
+    } else {
+        n.checked_add(by as usize)
+    }
+}
+
+// Where `(row, col)` lands on a `width` by `height` map tiled forever in
+// every direction.
+pub fn wrap(width: usize, height: usize, row: isize, col: isize) -> Spot {
+    Spot {
+        row: row.rem_euclid(height as isize) as usize,
+        col: col.rem_euclid(width as isize) as usize,
+    }
+}
+
+// Dense, row-major 2D storage.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Grid<T> {
+    width: usize,
+    height: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, cells: Vec<T>) -> Grid<T> {
+        assert!(
+            width > 0 && cells.len().is_multiple_of(width),
+            "cells don't fill whole rows"
+        );
+
+        Grid {
+            width,
+            height: cells.len() / width,
+            cells,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, spot: Spot) -> bool {
+        spot.row < self.height && spot.col < self.width
+    }
+
+    pub fn get(&self, spot: Spot) -> Option<&T> {
+        if self.contains(spot) {
+            Some(&self.cells[spot.row * self.width + spot.col])
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, spot: Spot) -> Option<&mut T> {
+        if self.contains(spot) {
+            Some(&mut self.cells[spot.row * self.width + spot.col])
+        } else {
+            None
+        }
+    }
+
+    // Treats the grid as tiled forever in every direction.
+    pub fn wrap(&self, row: isize, col: isize) -> Spot {
+        wrap(self.width, self.height, row, col)
+    }
+
+    pub fn get_wrapping(&self, row: isize, col: isize) -> &T {
+        &self[self.wrap(row, col)]
+    }
+
+    pub fn step(&self, spot: Spot, direction: Dir) -> Option<Spot> {
+        spot.look(direction).filter(|&s| self.contains(s))
+    }
+
+    pub fn rows(&self) -> impl Iterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Spot, &T)> {
+        let width = self.width;
+        self.cells.iter().enumerate().map(move |(i, cell)| {
+            (
+                Spot {
+                    row: i / width,
+                    col: i % width,
+                },
+                cell,
+            )
+        })
+    }
+
+    pub fn neighbors_4(&self, spot: Spot) -> impl Iterator<Item = Spot> + '_ {
+        Dir::CARDINAL
+            .iter()
+            .filter_map(move |&d| self.step(spot, d))
+    }
+
+    pub fn neighbors_8(&self, spot: Spot) -> impl Iterator<Item = Spot> + '_ {
+        Dir::ALL.iter().filter_map(move |&d| self.step(spot, d))
+    }
+
+    // Every spot from `spot` (not included) to the edge in `direction`.
+    pub fn ray(&self, spot: Spot, direction: Dir) -> impl Iterator<Item = (Spot, &T)> {
+        std::iter::successors(self.step(spot, direction), move |&s| {
+            self.step(s, direction)
+        })
+        .map(move |s| (s, &self[s]))
+    }
+
+    pub fn map<U>(&self, f: impl Fn(Spot, &T) -> U) -> Grid<U> {
+        Grid {
+            width: self.width,
+            height: self.height,
+            cells: self.iter().map(|(spot, cell)| f(spot, cell)).collect(),
+        }
+    }
+}
+
+impl<T: TryFrom<char>> Grid<T>
+where
+    T::Error: fmt::Display,
+{
+    // One row per line. Leading and trailing whitespace on a line is ignored,
+    // so indented test input works, and blank lines are skipped.
+    pub fn parse(day: u16, raw: &str) -> Result<Grid<T>> {
+        let mut width = None;
+        let mut cells = vec![];
+
+        for (y, line) in raw.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let offset = first_column(line);
+            let before = cells.len();
+
+            for (x, c) in line.trim().chars().enumerate() {
+                let cell = T::try_from(c).map_err(|e| {
+                    Error::parse(day, &c.to_string(), e.to_string()).at(y + 1, x + offset)
+                })?;
+                cells.push(cell);
+            }
+
+            let row_width = cells.len() - before;
+            match width {
+                None => width = Some(row_width),
+                Some(w) if w != row_width => {
+                    return Err(Error::parse(
+                        day,
+                        line.trim(),
+                        format!("expected {} squares like the first row", w),
+                    )
+                    .at(y + 1, 1))
+                }
+                _ => {}
+            }
+        }
+
+        match width {
+            Some(w) if w > 0 => Ok(Grid::new(w, cells)),
+            _ => Err(Error::Empty { day }),
+        }
+    }
+}
+
+impl<T> Index<Spot> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, spot: Spot) -> &T {
+        self.get(spot)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", spot))
+    }
+}
+
+impl<T> IndexMut<Spot> for Grid<T> {
+    fn index_mut(&mut self, spot: Spot) -> &mut T {
+        self.get_mut(spot)
+            .unwrap_or_else(|| panic!("{:?} is outside the grid", spot))
+    }
+}
+
+impl<T: Copy + Into<char>> fmt::Display for Grid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, row) in self.rows().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            for &cell in row {
+                write!(f, "{}", cell.into())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct Bit(bool);
+
+    impl TryFrom<char> for Bit {
+        type Error = String;
+        fn try_from(c: char) -> std::result::Result<Self, Self::Error> {
+            match c {
+                '0' => Ok(Bit(false)),
+                '1' => Ok(Bit(true)),
+                _ => Err("expected `0` or `1`".to_string()),
+            }
+        }
+    }
+
+    impl From<Bit> for char {
+        fn from(bit: Bit) -> char {
+            if bit.0 {
+                '1'
+            } else {
+                '0'
+            }
+        }
+    }
+
+    fn spot(row: usize, col: usize) -> Spot {
+        Spot { row, col }
+    }
+
+    #[test]
+    fn parse_and_display() {
+        let grid = Grid::<Bit>::parse(0, "  010\n  001").unwrap();
+
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[spot(0, 1)], Bit(true));
+        assert_eq!(grid.to_string(), "010\n001");
+        assert_eq!(
+            Grid::<Bit>::parse(0, "01\n 2").unwrap_err().to_string(),
+            "day 0 input, line 2 column 2: expected `0` or `1` [2]"
+        );
+        assert_eq!(
+            Grid::<Bit>::parse(0, "01\n0").unwrap_err().to_string(),
+            "day 0 input, line 2 column 1: expected 2 squares like the first row [0]"
+        );
+        assert_eq!(Grid::<Bit>::parse(0, ""), Err(Error::Empty { day: 0 }));
+        assert_eq!(
+            Grid::<Bit>::parse(0, "\n  \n"),
+            Err(Error::Empty { day: 0 })
+        );
+        assert_eq!(Grid::<Bit>::parse(0, "\n010\n001\n\n").unwrap(), grid);
+    }
+
+    #[test]
+    fn indexing() {
+        let grid = Grid::<Bit>::parse(0, "100\n000").unwrap();
+
+        assert_eq!(grid.get(spot(2, 0)), None);
+        assert_eq!(grid.get(spot(0, 3)), None);
+        assert_eq!(*grid.get_wrapping(-2, 3), Bit(true));
+        assert_eq!(grid.wrap(5, -1), spot(1, 2));
+    }
+
+    #[test]
+    fn neighbors_and_rays() {
+        let grid = Grid::<Bit>::parse(0, "000\n010\n000").unwrap();
+
+        assert_eq!(grid.neighbors_4(spot(1, 1)).count(), 4);
+        assert_eq!(grid.neighbors_8(spot(1, 1)).count(), 8);
+        assert_eq!(
+            grid.neighbors_8(spot(0, 0)).collect::<Vec<_>>(),
+            vec![spot(0, 1), spot(1, 1), spot(1, 0)]
+        );
+        assert_eq!(
+            grid.ray(spot(2, 2), Dir::LeftAndUp).collect::<Vec<_>>(),
+            vec![(spot(1, 1), &Bit(true)), (spot(0, 0), &Bit(false))]
+        );
+        assert_eq!(grid.ray(spot(0, 0), Dir::Up).count(), 0);
+    }
+}
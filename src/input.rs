@@ -0,0 +1,95 @@
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::PathBuf;
+
+pub const INPUTS_DIR_VAR: &str = "AOC_INPUTS";
+const DEFAULT_INPUTS_DIR: &str = "./src";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Source {
+    // `<dir>/day<N>.txt`
+    Dir(PathBuf),
+    File(PathBuf),
+    Stdin,
+}
+
+impl Source {
+    // `-` means stdin, like most command line tools.
+    pub fn from_arg(arg: &str) -> Source {
+        match arg {
+            "-" => Source::Stdin,
+            path => Source::File(PathBuf::from(path)),
+        }
+    }
+
+    // `--input` wins over `--inputs-dir`, which wins over $AOC_INPUTS.
+    pub fn resolve(input: Option<&str>, inputs_dir: Option<&str>) -> Source {
+        if let Some(arg) = input {
+            return Source::from_arg(arg);
+        }
+
+        let dir = inputs_dir
+            .map(|d| d.to_string())
+            .or_else(|| env::var(INPUTS_DIR_VAR).ok())
+            .unwrap_or_else(|| DEFAULT_INPUTS_DIR.to_string());
+
+        Source::Dir(PathBuf::from(dir))
+    }
+
+    pub fn describe(&self, day: u16) -> String {
+        match self {
+            Source::Dir(dir) => dir.join(format!("day{}.txt", day)).display().to_string(),
+            Source::File(path) => path.display().to_string(),
+            Source::Stdin => "<stdin>".to_string(),
+        }
+    }
+
+    pub fn load(&self, day: u16) -> io::Result<String> {
+        match self {
+            Source::Dir(dir) => fs::read_to_string(dir.join(format!("day{}.txt", day))),
+            Source::File(path) => fs::read_to_string(path),
+            Source::Stdin => {
+                let mut raw = String::new();
+                io::stdin().read_to_string(&mut raw)?;
+                Ok(raw)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve() {
+        assert_eq!(Source::resolve(Some("-"), None), Source::Stdin);
+        assert_eq!(
+            Source::resolve(Some("day1.txt"), Some("inputs")),
+            Source::File(PathBuf::from("day1.txt"))
+        );
+        assert_eq!(
+            Source::resolve(None, Some("inputs")),
+            Source::Dir(PathBuf::from("inputs"))
+        );
+    }
+
+    #[test]
+    fn load_from_dir() {
+        let dir = env::temp_dir().join(format!("aoc_2020_inputs_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("day3.txt"), "..#\n").unwrap();
+
+        let source = Source::Dir(dir.clone());
+
+        assert_eq!(source.load(3).unwrap(), "..#\n");
+        assert!(source.load(4).is_err());
+        assert_eq!(
+            source.describe(4),
+            dir.join("day4.txt").display().to_string()
+        );
+
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,31 @@
+#[macro_use]
+extern crate lazy_static;
+#[macro_use]
+extern crate maplit;
+pub mod answers;
+pub mod bench;
+pub mod day1;
+pub mod day10;
+pub mod day11;
+pub mod day2;
+pub mod day3;
+pub mod day4;
+pub mod day5;
+pub mod day6;
+pub mod day7;
+pub mod day8;
+pub mod day9;
+pub mod error;
+pub mod grid;
+pub mod input;
+pub mod parallel;
+pub mod report;
+pub mod solution;
+
+pub use day11::{SeatingSystem, Spot};
+pub use day4::{Passport, RawRecord};
+pub use day5::Seat;
+pub use day7::Rule;
+pub use day8::{OpCode, Program};
+pub use error::Error;
+pub use solution::{Runnable, Solution, SOLUTIONS};
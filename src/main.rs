@@ -1,39 +1,191 @@
-#[macro_use]
-extern crate lazy_static;
-#[macro_use]
-extern crate maplit;
-mod day1;
-mod day10;
-mod day11;
-mod day2;
-mod day3;
-mod day4;
-mod day5;
-mod day6;
-mod day7;
-mod day8;
-mod day9;
+mod cli;
+
+use std::fs;
+use std::process;
+
+use aoc_2020::answers::{Answers, Verdict};
+use aoc_2020::bench::{self, Baseline};
+use aoc_2020::day2::{self, Day2};
+use aoc_2020::day4::{self, Day4, Schema};
+use aoc_2020::report::{self, Format, Record};
+use aoc_2020::{input, parallel, solution, Runnable, Solution};
+
+fn load<T: std::str::FromStr<Err = String>>(path: &str, what: &str) -> T {
+    match fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|raw| raw.parse::<T>())
+    {
+        Ok(loaded) => loaded,
+        Err(e) => {
+            eprintln!("error: couldn't load {} from {}: {}", what, path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn load_input(source: &input::Source, day: u16) -> String {
+    source.load(day).unwrap_or_else(|e| {
+        eprintln!("error: couldn't read {}: {}", source.describe(day), e);
+        process::exit(1);
+    })
+}
+
+// Day 4 with a custom schema stands in for the usual one.
+fn find(day: u16, schema: Option<&day4::WithSchema>) -> &dyn Runnable {
+    match schema {
+        Some(with_schema) if day == Day4::DAY => with_schema,
+        _ => solution::find(day).unwrap(),
+    }
+}
+
+fn run_benchmarks(
+    options: &cli::Options,
+    bench_options: &cli::Bench,
+    source: &input::Source,
+    schema: Option<&day4::WithSchema>,
+) {
+    let baseline = bench_options
+        .baseline
+        .as_ref()
+        .map(|path| load::<Baseline>(path, "baseline"));
+    let mut benches = vec![];
+
+    for &day in &options.days {
+        let solution = find(day, schema);
+        let raw = load_input(source, day);
+
+        for &part in &options.parts {
+            match bench::bench(solution, part, &raw, bench_options.iterations) {
+                Ok(b) => benches.push(b),
+                Err(e) => {
+                    eprintln!("error: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+    }
+
+    print!("{}", bench::render(&benches, baseline.as_ref()));
+
+    if let Some(path) = &bench_options.save_baseline {
+        if let Err(e) = fs::write(path, Baseline::from_benches(&benches).to_toml()) {
+            eprintln!("error: couldn't save baseline to {}: {}", path, e);
+            process::exit(1);
+        }
+    }
+}
+
+fn run_audit(options: &cli::Options, source: &input::Source) {
+    let entries = Day2::parse(&load_input(source, Day2::DAY)).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    });
+    let audits = options
+        .parts
+        .iter()
+        .map(|&part| day2::audit(&entries, part))
+        .collect::<Vec<_>>();
+
+    if options.format == Format::Csv {
+        print!("{}", day2::AUDIT_CSV_HEADER);
+        for audit in &audits {
+            print!("{}", audit.csv_rows());
+        }
+    } else {
+        let rendered = audits.iter().map(|a| a.to_string()).collect::<Vec<_>>();
+        print!("{}", rendered.join("\n"));
+    }
+}
+
+fn run_passports(options: &cli::Options, source: &input::Source, schema: &Schema) {
+    let report = day4::report(&load_input(source, Day4::DAY), schema).unwrap_or_else(|e| {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    });
+
+    if options.format == Format::Json {
+        print!("{}", report::passports_json(&report));
+    } else {
+        print!("{}", report);
+    }
+}
 
 fn main() {
-    // https://notes.iveselov.info/programming/time_it-a-case-study-in-rust-macros#with-tt-specifier
-    macro_rules! timed {
-     ($context:literal, $b:block) => {
-        println!("{}:", $context);
-        let timer = std::time::Instant::now();
-        $b
-        println!("took {:?}\n", timer.elapsed());
-    };
-    };
+    let available = solution::SOLUTIONS
+        .iter()
+        .map(|s| s.day())
+        .collect::<Vec<_>>();
 
-    macro_rules! run {
-        ($($module:tt),*) => (
-            $(
-                println!("Day {}", $module::DAY);
-                timed!("Part 1", {$module::part_1()});
-                timed!("Part 2", {$module::part_2()});
-            )*
-        );
+    let options = match cli::parse_args(std::env::args().skip(1), &available) {
+        Ok(options) => options,
+        Err(cli::CliError::Help) => {
+            println!("{}", cli::USAGE);
+            return;
+        }
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, cli::USAGE);
+            process::exit(2);
+        }
     };
 
-    run!(day1, day2, day3, day4, day5, day6, day7, day8, day9, day10, day11);
+    let source = input::Source::resolve(options.input.as_deref(), options.inputs_dir.as_deref());
+
+    let schema = options
+        .schema
+        .as_ref()
+        .map(|path| day4::WithSchema(load::<Schema>(path, "day 4 schema")));
+
+    if let Some(bench_options) = &options.bench {
+        run_benchmarks(&options, bench_options, &source, schema.as_ref());
+        return;
+    }
+
+    if options.audit {
+        run_audit(&options, &source);
+        return;
+    }
+
+    if options.passports {
+        let schema = schema.as_ref().map_or(day4::default_schema(), |s| &s.0);
+        run_passports(&options, &source, schema);
+        return;
+    }
+
+    let answers = options
+        .verify
+        .as_ref()
+        .map(|path| load::<Answers>(path, "answers"));
+    let inputs = options
+        .days
+        .iter()
+        .map(|&day| (day, load_input(&source, day)))
+        .collect::<Vec<_>>();
+    let tasks = inputs
+        .iter()
+        .flat_map(|(day, raw)| options.parts.iter().map(move |&part| (*day, part, raw)))
+        .collect::<Vec<_>>();
+
+    let records = parallel::map_in_order(&tasks, options.jobs, |&(day, part, raw)| {
+        let outcome = find(day, schema.as_ref()).run(part, raw);
+        let verdict = match (&answers, &outcome) {
+            (Some(answers), Ok(run)) => Some(answers.check(day, part, &run.answer)),
+            _ => None,
+        };
+
+        Record {
+            day,
+            part,
+            outcome,
+            verdict,
+        }
+    });
+
+    print!("{}", report::render(options.format, &records));
+
+    if records
+        .iter()
+        .any(|r| r.outcome.is_err() || matches!(r.verdict, Some(Verdict::Fail { .. })))
+    {
+        process::exit(1);
+    }
 }
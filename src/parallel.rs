@@ -0,0 +1,68 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+// Runs `f` over every task on up to `jobs` threads. Results come back in the
+// same order as `tasks`, however the work happened to be scheduled.
+pub fn map_in_order<T, R, F>(tasks: &[T], jobs: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let jobs = jobs.max(1).min(tasks.len());
+    if jobs <= 1 {
+        return tasks.iter().map(f).collect();
+    }
+
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new((0..tasks.len()).map(|_| None).collect::<Vec<Option<R>>>());
+
+    thread::scope(|scope| {
+        for _ in 0..jobs {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let task = match tasks.get(index) {
+                    Some(task) => task,
+                    None => break,
+                };
+
+                let result = f(task);
+                results.lock().unwrap()[index] = Some(result);
+            });
+        }
+    });
+
+    results
+        .into_inner()
+        .unwrap()
+        .into_iter()
+        .map(|r| r.expect("every task runs exactly once"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn keeps_order() {
+        let tasks = (0..20u64).collect::<Vec<_>>();
+
+        // Make early tasks finish last.
+        let results = map_in_order(&tasks, 4, |&n| {
+            thread::sleep(Duration::from_millis(20 - n));
+            n * 2
+        });
+
+        assert_eq!(results, (0..20u64).map(|n| n * 2).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn handles_edge_cases() {
+        assert_eq!(map_in_order(&[] as &[u8], 8, |&n| n), vec![]);
+        assert_eq!(map_in_order(&[1, 2, 3], 0, |&n| n + 1), vec![2, 3, 4]);
+        assert_eq!(map_in_order(&[1, 2, 3], 100, |&n| n + 1), vec![2, 3, 4]);
+    }
+}
@@ -0,0 +1,338 @@
+use std::fmt::Write;
+use std::str::FromStr;
+
+use crate::answers::Verdict;
+use crate::day4;
+use crate::error::Error;
+use crate::solution::Run;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Text,
+    Json,
+    Csv,
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(raw: &str) -> Result<Self, Self::Err> {
+        match raw {
+            "text" => Ok(Format::Text),
+            "json" => Ok(Format::Json),
+            "csv" => Ok(Format::Csv),
+            x => Err(format!(
+                "[{}] isn't a valid format, expected text, json or csv",
+                x
+            )),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Record {
+    pub day: u16,
+    pub part: u8,
+    pub outcome: Result<Run, Error>,
+    // Only set when checking against known answers.
+    pub verdict: Option<Verdict>,
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+fn json_verdict(verdict: &Option<Verdict>) -> String {
+    match verdict {
+        None => r#""verdict":null,"expected":null"#.to_string(),
+        Some(Verdict::Pass) => r#""verdict":"pass","expected":null"#.to_string(),
+        Some(Verdict::Unknown) => r#""verdict":"unknown","expected":null"#.to_string(),
+        Some(Verdict::Fail { expected }) => {
+            format!(r#""verdict":"fail","expected":{}"#, json_string(expected))
+        }
+    }
+}
+
+pub(crate) fn csv_field(s: &str) -> String {
+    if s.contains(&[',', '"', '\n', '\r'][..]) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn text(records: &[Record]) -> String {
+    let mut out = String::new();
+    let mut current_day = None;
+
+    for Record {
+        day,
+        part,
+        outcome,
+        verdict,
+    } in records
+    {
+        if current_day != Some(day) {
+            writeln!(out, "Day {}", day).unwrap();
+            current_day = Some(day);
+        }
+        writeln!(out, "Part {}:", part).unwrap();
+        match outcome {
+            Ok(run) => writeln!(
+                out,
+                "{}{}\ntook {:?} (parse {:?}, solve {:?})\n",
+                run.answer,
+                verdict
+                    .as_ref()
+                    .map_or_else(String::new, |v| format!(" [{}]", v)),
+                run.parse_time + run.solve_time,
+                run.parse_time,
+                run.solve_time
+            ),
+            Err(e) => writeln!(out, "error: {}\n", e),
+        }
+        .unwrap();
+    }
+
+    out
+}
+
+fn json(records: &[Record]) -> String {
+    let rows = records
+        .iter()
+        .map(|record| match &record.outcome {
+            Ok(run) => format!(
+                r#"{{"day":{},"part":{},"answer":{},"parse_ns":{},"solve_ns":{},"error":null,{}}}"#,
+                record.day,
+                record.part,
+                json_string(&run.answer),
+                run.parse_time.as_nanos(),
+                run.solve_time.as_nanos(),
+                json_verdict(&record.verdict)
+            ),
+            Err(e) => format!(
+                r#"{{"day":{},"part":{},"answer":null,"parse_ns":null,"solve_ns":null,"error":{},{}}}"#,
+                record.day,
+                record.part,
+                json_string(&e.to_string()),
+                json_verdict(&record.verdict)
+            ),
+        })
+        .collect::<Vec<_>>();
+
+    format!("[{}]\n", rows.join(","))
+}
+
+fn csv(records: &[Record]) -> String {
+    let mut out = "day,part,answer,parse_ns,solve_ns,error,verdict,expected\n".to_string();
+
+    for Record {
+        day,
+        part,
+        outcome,
+        verdict,
+    } in records
+    {
+        let (verdict, expected) = match verdict {
+            None => ("", ""),
+            Some(Verdict::Pass) => ("pass", ""),
+            Some(Verdict::Unknown) => ("unknown", ""),
+            Some(Verdict::Fail { expected }) => ("fail", expected.as_str()),
+        };
+        match outcome {
+            Ok(run) => writeln!(
+                out,
+                "{},{},{},{},{},,{},{}",
+                day,
+                part,
+                csv_field(&run.answer),
+                run.parse_time.as_nanos(),
+                run.solve_time.as_nanos(),
+                verdict,
+                csv_field(expected)
+            ),
+            Err(e) => writeln!(
+                out,
+                "{},{},,,,{},{},{}",
+                day,
+                part,
+                csv_field(&e.to_string()),
+                verdict,
+                csv_field(expected)
+            ),
+        }
+        .unwrap();
+    }
+
+    out
+}
+
+fn json_strings(list: &[String]) -> String {
+    let list = list.iter().map(|s| json_string(s)).collect::<Vec<_>>();
+    format!("[{}]", list.join(","))
+}
+
+fn json_diagnosis(diagnosis: &day4::Diagnosis) -> String {
+    let invalid = diagnosis
+        .invalid
+        .iter()
+        .map(|field| {
+            format!(
+                r#"{{"field":{},"value":{},"reason":{}}}"#,
+                json_string(&field.key),
+                json_string(&field.value),
+                json_string(&field.reason)
+            )
+        })
+        .collect::<Vec<_>>();
+
+    format!(
+        r#"{{"line":{},"valid":{},"missing":{},"invalid":[{}],"unknown":{},"untyped":{}}}"#,
+        diagnosis.line,
+        diagnosis.is_valid(),
+        json_strings(&diagnosis.missing),
+        invalid.join(","),
+        json_strings(&diagnosis.unknown),
+        diagnosis
+            .untyped
+            .as_deref()
+            .map_or("null".to_string(), json_string)
+    )
+}
+
+// The day 4 `--passports` report.
+pub fn passports_json(report: &day4::Report) -> String {
+    let diagnoses = report
+        .diagnoses
+        .iter()
+        .map(json_diagnosis)
+        .collect::<Vec<_>>();
+
+    format!(
+        r#"{{"checked":{},"valid":{},"passports":[{}]}}"#,
+        report.diagnoses.len(),
+        report.valid(),
+        diagnoses.join(",")
+    ) + "\n"
+}
+
+pub fn render(format: Format, records: &[Record]) -> String {
+    match format {
+        Format::Text => text(records),
+        Format::Json => json(records),
+        Format::Csv => csv(records),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn records() -> Vec<Record> {
+        vec![
+            Record {
+                day: 1,
+                part: 1,
+                outcome: Ok(Run {
+                    answer: "514579".to_string(),
+                    parse_time: Duration::from_nanos(1500),
+                    solve_time: Duration::from_nanos(20),
+                }),
+                verdict: None,
+            },
+            Record {
+                day: 8,
+                part: 2,
+                outcome: Err(Error::parse(8, "mul", "not a valid op code")),
+                verdict: None,
+            },
+        ]
+    }
+
+    #[test]
+    fn json() {
+        assert_eq!(
+            render(Format::Json, &records()),
+            concat!(
+                r#"[{"day":1,"part":1,"answer":"514579","parse_ns":1500,"solve_ns":20,"error":null,"#,
+                r#""verdict":null,"expected":null},"#,
+                r#"{"day":8,"part":2,"answer":null,"parse_ns":null,"solve_ns":null,"#,
+                r#""error":"day 8 input, line 1 column 1: not a valid op code [mul]","#,
+                r#""verdict":null,"expected":null}]"#,
+                "\n"
+            )
+        );
+        assert_eq!(json_string("a\"b\\\n"), r#""a\"b\\\n""#);
+    }
+
+    #[test]
+    fn passports_json() {
+        let report = day4::report("byr:1937\n\nhcl:\"x\"", day4::default_schema()).unwrap();
+
+        assert_eq!(
+            super::passports_json(&report),
+            concat!(
+                r#"{"checked":2,"valid":0,"passports":["#,
+                r#"{"line":1,"valid":false,"missing":["ecl","eyr","hcl","hgt","iyr","pid"],"invalid":[],"unknown":[],"untyped":null},"#,
+                r#"{"line":3,"valid":false,"missing":["byr","ecl","eyr","hgt","iyr","pid"],"#,
+                r#""invalid":[{"field":"hcl","value":"\"x\"","reason":"\"x\" doesn't match ^#[0-9a-f]{6}$"}],"unknown":[],"#,
+                r#""untyped":null}"#,
+                "]}\n"
+            )
+        );
+    }
+
+    #[test]
+    fn csv() {
+        assert_eq!(
+            render(Format::Csv, &records()),
+            concat!(
+                "day,part,answer,parse_ns,solve_ns,error,verdict,expected\n",
+                "1,1,514579,1500,20,,,\n",
+                "8,2,,,,\"day 8 input, line 1 column 1: not a valid op code [mul]\",,\n"
+            )
+        );
+    }
+
+    #[test]
+    fn text() {
+        let rendered = render(Format::Text, &records());
+
+        assert!(
+            rendered.starts_with("Day 1\nPart 1:\n514579\ntook 1.52µs (parse 1.5µs, solve 20ns)\n")
+        );
+        assert!(rendered.contains("Day 8\nPart 2:\nerror: day 8 input"));
+    }
+
+    #[test]
+    fn verdicts() {
+        let mut records = records();
+        records[0].verdict = Some(Verdict::Fail {
+            expected: "1,2".to_string(),
+        });
+
+        assert!(render(Format::Text, &records).contains("514579 [FAIL (expected 1,2)]\n"));
+        assert!(render(Format::Json, &records).contains(r#""verdict":"fail","expected":"1,2""#));
+        assert!(render(Format::Csv, &records).contains("1,1,514579,1500,20,,fail,\"1,2\"\n"));
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!("json".parse::<Format>(), Ok(Format::Json));
+        assert!("yaml".parse::<Format>().is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::fmt::Display;
+use std::time::{Duration, Instant};
+
+use crate::error::{Error, Result};
+use crate::{day1, day10, day11, day2, day3, day4, day5, day6, day7, day8, day9};
+
+pub trait Solution {
+    const DAY: u16;
+
+    type Input;
+    type Part1: Display;
+    type Part2: Display;
+
+    fn parse(raw: &str) -> Result<Self::Input>;
+    // Input that parses can still have no answer, that's an `Error` too.
+    fn part_1(input: &Self::Input) -> Result<Self::Part1>;
+    fn part_2(input: &Self::Input) -> Result<Self::Part2>;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Run {
+    pub answer: String,
+    pub parse_time: Duration,
+    pub solve_time: Duration,
+}
+
+// `Solution` has associated types, so it can't be used as a trait object.
+// This erases them so every day can sit in the same registry.
+pub trait Runnable: Sync {
+    fn day(&self) -> u16;
+    fn run(&self, part: u8, raw: &str) -> Result<Run>;
+}
+
+impl<S: Solution + Sync> Runnable for S {
+    fn day(&self) -> u16 {
+        S::DAY
+    }
+
+    fn run(&self, part: u8, raw: &str) -> Result<Run> {
+        timed(raw, S::parse, |input| match part {
+            1 => S::part_1(input).map(|a| a.to_string()),
+            2 => S::part_2(input).map(|a| a.to_string()),
+            x => Err(invalid_part(S::DAY, x)),
+        })
+    }
+}
+
+// Times parsing and solving separately, for `Runnable`s that aren't a plain
+// `Solution`.
+pub fn timed<I>(
+    raw: &str,
+    parse: impl FnOnce(&str) -> Result<I>,
+    solve: impl FnOnce(&I) -> Result<String>,
+) -> Result<Run> {
+    let timer = Instant::now();
+    let input = parse(raw)?;
+    let parse_time = timer.elapsed();
+
+    let timer = Instant::now();
+    let answer = solve(&input)?;
+
+    Ok(Run {
+        answer,
+        parse_time,
+        solve_time: timer.elapsed(),
+    })
+}
+
+// Every day has exactly two parts.
+pub fn invalid_part(day: u16, part: u8) -> Error {
+    Error::Unsupported {
+        day,
+        reason: format!("there's no part {}, only 1 and 2", part),
+    }
+}
+
+pub static SOLUTIONS: [&dyn Runnable; 11] = [
+    &day1::Day1,
+    &day2::Day2,
+    &day3::Day3,
+    &day4::Day4,
+    &day5::Day5,
+    &day6::Day6,
+    &day7::Day7,
+    &day8::Day8,
+    &day9::Day9,
+    &day10::Day10,
+    &day11::Day11,
+];
+
+pub fn find(day: u16) -> Option<&'static dyn Runnable> {
+    SOLUTIONS.iter().copied().find(|s| s.day() == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn registry_is_ordered_and_unique() {
+        let days = SOLUTIONS.iter().map(|s| s.day()).collect::<Vec<_>>();
+
+        assert_eq!(days, (1..=11).collect::<Vec<_>>());
+        assert_eq!(find(7).map(|s| s.day()), Some(7));
+        assert!(find(12).is_none());
+    }
+
+    #[test]
+    fn runs_through_the_registry() {
+        let day1 = find(1).unwrap();
+
+        assert_eq!(
+            day1.run(1, "1721\n979\n366\n299\n675\n1456")
+                .unwrap()
+                .answer,
+            "514579"
+        );
+        assert_eq!(
+            day1.run(2, "1721\n979\n366\n299\n675\n1456")
+                .unwrap()
+                .answer,
+            "241861950"
+        );
+        assert!(day1.run(1, "1721\nabc").is_err());
+        assert_eq!(
+            day1.run(3, "1721\n979").unwrap_err().to_string(),
+            "day 1: there's no part 3, only 1 and 2"
+        );
+    }
+}